@@ -0,0 +1,236 @@
+use hcl::edit::parser;
+use hcl::edit::structure::Body;
+use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use crate::json;
+use crate::module::{Module, ModuleFile};
+use crate::suppression::Suppressions;
+use crate::terraform_json;
+use crate::utils::TerraformUtils;
+
+/// A file parsed once and shared by every rule that needs its AST
+pub struct ParsedDocument {
+    /// Parsed HCL body, or None if the file is not valid HCL
+    pub body: Option<Body>,
+    /// Why parsing failed, when it did
+    pub error: Option<SyntaxError>,
+    /// Inline `forseti-ignore` / `tflint-ignore` comments found in the file
+    pub suppressions: Suppressions,
+}
+
+impl ParsedDocument {
+    fn parse(uri: &str, text: &str) -> Self {
+        let parsed = if TerraformUtils::is_state_file(uri) {
+            // State is plain JSON with no configuration body; only its syntax is checked
+            json::parse(text).map(|_| Body::new()).map_err(SyntaxError::from)
+        } else if TerraformUtils::is_json_syntax(uri) {
+            terraform_json::parse_body(text, TerraformUtils::is_tfvars(uri))
+        } else {
+            TerraformUtils::parse_hcl(text).map_err(SyntaxError::from)
+        };
+        let (body, error) = match parsed {
+            Ok(body) => (Some(body), None),
+            Err(err) => (None, Some(err)),
+        };
+        let suppressions = Suppressions::parse(text, body.as_ref());
+
+        Self {
+            body,
+            error,
+            suppressions,
+        }
+    }
+}
+
+/// An HCL (or Terraform JSON) parse failure with the location reported by the parser
+#[derive(Debug)]
+pub struct SyntaxError {
+    pub message: String,
+    /// Byte offset of the offending input
+    pub offset: usize,
+}
+
+impl From<parser::Error> for SyntaxError {
+    fn from(err: parser::Error) -> Self {
+        Self {
+            message: err.message().to_string(),
+            offset: err.location().offset(),
+        }
+    }
+}
+
+impl From<json::JsonError> for SyntaxError {
+    fn from(err: json::JsonError) -> Self {
+        Self {
+            message: err.message,
+            offset: err.offset,
+        }
+    }
+}
+
+struct CacheEntry {
+    content_hash: u64,
+    document: Arc<ParsedDocument>,
+}
+
+struct ModuleEntry {
+    fingerprint: u64,
+    /// The module as it is on disk
+    module: Arc<Module>,
+    /// The last module built with an in-memory file content: uri, content hash and module
+    overlay: Option<(String, u64, Arc<Module>)>,
+}
+
+/// Per-file parse cache keyed by URI and content hash.
+///
+/// The ruleset creates a single cache and hands it to each HCL rule, so a file
+/// is parsed once per lint run no matter how many rules inspect it. An entry is
+/// replaced as soon as the content for its URI changes.
+///
+/// Modules (all configuration files of a directory) are cached the same way,
+/// keyed by directory and the directory's metadata. A file whose
+/// in-memory content differs from disk is laid over the cached module instead
+/// of invalidating it.
+#[derive(Default)]
+pub struct DocumentCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    modules: Mutex<HashMap<String, ModuleEntry>>,
+}
+
+impl DocumentCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return the parsed document for `uri`, parsing `text` only on a cache miss
+    pub fn get_or_parse(&self, uri: &str, text: &str) -> Arc<ParsedDocument> {
+        let content_hash = Self::hash_content(text);
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(entry) = entries.get(uri)
+            && entry.content_hash == content_hash
+        {
+            return Arc::clone(&entry.document);
+        }
+
+        let document = Arc::new(ParsedDocument::parse(uri, text));
+        entries.insert(
+            uri.to_string(),
+            CacheEntry {
+                content_hash,
+                document: Arc::clone(&document),
+            },
+        );
+        document
+    }
+
+    /// Return the module containing `uri`, with `text` standing in for that file's content.
+    ///
+    /// URIs outside the local filesystem form a module of their own.
+    pub fn module_for(&self, uri: &str, text: &str) -> Arc<Module> {
+        let Some(directory) = Module::directory_of(uri) else {
+            let file = ModuleFile::new(uri.to_string(), text.to_string(), self.get_or_parse(uri, text));
+            return Arc::new(Module::from_files(String::new(), vec![file]));
+        };
+
+        let fingerprint = Self::module_fingerprint(&directory);
+        let key = directory.display().to_string();
+        let mut modules = self.modules.lock().unwrap_or_else(|e| e.into_inner());
+
+        let entry = match modules.get_mut(&key) {
+            Some(entry) if entry.fingerprint == fingerprint => entry,
+            _ => {
+                let entry = ModuleEntry {
+                    fingerprint,
+                    module: Arc::new(Module::load(&directory, self)),
+                    overlay: None,
+                };
+                modules.entry(key).insert_entry(entry).into_mut()
+            }
+        };
+
+        if entry.module.has_content(uri, text) {
+            return Arc::clone(&entry.module);
+        }
+
+        let content_hash = Self::hash_content(text);
+        if let Some((overlay_uri, overlay_hash, module)) = &entry.overlay
+            && overlay_uri == uri
+            && *overlay_hash == content_hash
+        {
+            return Arc::clone(module);
+        }
+
+        let module = Arc::new(entry.module.with_overlay(uri, text, self));
+        entry.overlay = Some((uri.to_string(), content_hash, Arc::clone(&module)));
+        module
+    }
+
+    /// Change detector for a module: the directory's modification time, which moves
+    /// whenever a file is added, removed or renamed (as editors do on save). One stat
+    /// per lookup keeps module rules linear in the number of files; the file being
+    /// checked always contributes its in-memory text.
+    fn module_fingerprint(directory: &std::path::Path) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        std::fs::metadata(directory)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn hash_content(text: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        text.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn documents_are_parsed_once_per_content() {
+        let cache = DocumentCache::new();
+        let first = cache.get_or_parse("file:///main.tf", "variable \"a\" {}\n");
+        assert!(first.body.is_some());
+        assert!(Arc::ptr_eq(&first, &cache.get_or_parse("file:///main.tf", "variable \"a\" {}\n")));
+
+        // Same content under another URI, then new content under the same URI
+        assert!(!Arc::ptr_eq(&first, &cache.get_or_parse("file:///other.tf", "variable \"a\" {}\n")));
+        let edited = cache.get_or_parse("file:///main.tf", "variable \"a\" {\n");
+        assert!(!Arc::ptr_eq(&first, &edited));
+        assert!(edited.body.is_none());
+        assert!(edited.error.is_some());
+    }
+
+    #[test]
+    fn modules_are_reused_across_files_and_overlays() {
+        let directory = std::env::temp_dir().join(format!("forseti-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("a.tf"), "variable \"a\" {}\n").unwrap();
+        std::fs::write(directory.join("b.tf"), "variable \"b\" {}\n").unwrap();
+        let uri = |name: &str| format!("file://{}", directory.join(name).display());
+
+        let cache = DocumentCache::new();
+        let a = cache.module_for(&uri("a.tf"), "variable \"a\" {}\n");
+        let b = cache.module_for(&uri("b.tf"), "variable \"b\" {}\n");
+        assert!(Arc::ptr_eq(&a, &b));
+
+        let edited = cache.module_for(&uri("a.tf"), "variable \"c\" {}\n");
+        assert!(!Arc::ptr_eq(&a, &edited));
+        assert!(Arc::ptr_eq(&edited, &cache.module_for(&uri("a.tf"), "variable \"c\" {}\n")));
+        assert_eq!(edited.files.len(), 2);
+        assert!(edited.files.iter().any(|file| file.text.contains("\"c\"")));
+        assert!(Arc::ptr_eq(&a, &cache.module_for(&uri("b.tf"), "variable \"b\" {}\n")));
+
+        let unsaved = cache.module_for(&uri("new.tf"), "locals {}\n");
+        assert_eq!(unsaved.files.len(), 3);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
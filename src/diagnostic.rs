@@ -0,0 +1,124 @@
+use forseti_sdk::core::{Diagnostic, Position, Range, SuggestFix};
+use forseti_sdk::ruleset::RuleContext;
+use serde_json::Value;
+
+use crate::utils::TerraformUtils;
+
+/// Severities a rule can be configured with, in order of importance
+pub const SEVERITIES: [&str; 4] = ["off", "info", "warn", "error"];
+
+/// Resolve the severity from a rule config (`"warn"` or `["warn", { ... }]`).
+///
+/// Returns None when the rule is turned off or the severity is not recognised.
+pub fn configured_severity(config: &Value) -> Option<&'static str> {
+    let severity = match config {
+        Value::Array(items) => items.first()?.as_str()?,
+        other => other.as_str()?,
+    };
+    SEVERITIES
+        .into_iter()
+        .find(|s| *s == severity)
+        .filter(|s| *s != "off")
+}
+
+/// Builder shared by every rule so diagnostics carry the configured severity
+pub struct DiagnosticBuilder {
+    rule_id: String,
+    message: String,
+    range: Range,
+    code: Option<String>,
+    suggest: Option<Vec<SuggestFix>>,
+}
+
+impl DiagnosticBuilder {
+    pub fn new(rule_id: &str, message: impl Into<String>) -> Self {
+        let origin = Position {
+            line: 0,
+            character: 0,
+        };
+        Self {
+            rule_id: rule_id.to_string(),
+            message: message.into(),
+            range: Range {
+                start: origin,
+                end: origin,
+            },
+            code: None,
+            suggest: None,
+        }
+    }
+
+    pub fn with_range(mut self, range: Range) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Locate the diagnostic at a byte span of `text`
+    pub fn with_span(self, span: &std::ops::Range<usize>, text: &str) -> Self {
+        self.with_range(TerraformUtils::span_to_range(span, text))
+    }
+
+    pub fn with_code(mut self, code: &str) -> Self {
+        self.code = Some(code.to_string());
+        self
+    }
+
+    pub fn with_suggestions(mut self, suggest: Option<Vec<SuggestFix>>) -> Self {
+        self.suggest = suggest;
+        self
+    }
+
+    /// Finish the diagnostic with an explicit severity
+    pub fn build(self, severity: &str) -> Diagnostic {
+        Diagnostic {
+            docs_url: Some(format!(
+                "https://forseti.dev/rules/terraform/{}",
+                self.rule_id
+            )),
+            rule_id: self.rule_id,
+            message: self.message,
+            severity: severity.to_string(),
+            range: self.range,
+            code: self.code,
+            suggest: self.suggest,
+        }
+    }
+
+    /// Report with the severity configured for the rule; nothing is reported when it is off
+    pub fn report(self, ctx: &mut RuleContext) {
+        if let Some(severity) = configured_severity(ctx.options) {
+            ctx.report(self.build(severity));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::DocumentCache;
+    use crate::rules::VariableDescriptionRequiredRule;
+    use crate::testing::run;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn severity_comes_from_the_rule_config() {
+        assert_eq!(configured_severity(&json!("info")), Some("info"));
+        assert_eq!(configured_severity(&json!(["warn", { "pattern": ".*" }])), Some("warn"));
+        assert_eq!(configured_severity(&json!("off")), None);
+        assert_eq!(configured_severity(&json!("fatal")), None);
+        assert_eq!(configured_severity(&json!([])), None);
+
+        let rule = VariableDescriptionRequiredRule::new(Arc::new(DocumentCache::new()));
+        let severities = |config| {
+            run(&rule, "file:///variables.tf", "variable \"region\" {}\n", config)
+                .into_iter()
+                .map(|d| d.severity)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(severities(json!("info")), ["info"]);
+        assert_eq!(severities(json!(["warn"])), ["warn"]);
+        assert_eq!(severities(json!("error")), ["error"]);
+        assert!(severities(json!("off")).is_empty());
+    }
+}
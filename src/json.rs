@@ -0,0 +1,388 @@
+//! A small JSON parser that keeps the byte span of every value and key, so
+//! diagnostics can point back into JSON documents.
+
+use std::ops::Range;
+
+/// Deepest nesting of arrays and objects accepted, so that a hostile document is
+/// rejected instead of exhausting the stack
+const MAX_DEPTH: usize = 128;
+
+/// A JSON value and where it appears in the source
+#[derive(Debug, Clone)]
+pub struct JsonValue {
+    pub kind: JsonKind,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub enum JsonKind {
+    Null,
+    Bool(bool),
+    Number,
+    /// The decoded string
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(Vec<JsonMember>),
+}
+
+/// One `"key": value` pair of an object, in source order
+#[derive(Debug, Clone)]
+pub struct JsonMember {
+    pub key: String,
+    /// Span of the key including its quotes
+    pub key_span: Range<usize>,
+    pub value: JsonValue,
+}
+
+/// Why a document is not valid JSON
+#[derive(Debug)]
+pub struct JsonError {
+    pub message: String,
+    pub offset: usize,
+}
+
+impl JsonValue {
+    pub fn as_object(&self) -> Option<&[JsonMember]> {
+        match &self.kind {
+            JsonKind::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&[JsonValue]> {
+        match &self.kind {
+            JsonKind::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match &self.kind {
+            JsonKind::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match &self.kind {
+            JsonKind::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Value of the member named `key`, when this is an object
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object()?
+            .iter()
+            .find(|member| member.key == key)
+            .map(|member| &member.value)
+    }
+}
+
+/// Parse a complete JSON document
+pub fn parse(text: &str) -> Result<JsonValue, JsonError> {
+    let mut parser = Parser { text, pos: 0, depth: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error("unexpected content after the JSON document"));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    pos: usize,
+    /// Arrays and objects currently open
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            message: message.to_string(),
+            offset: self.pos.min(self.text.len()),
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), JsonError> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn value(&mut self) -> Result<JsonValue, JsonError> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let kind = match self.peek() {
+            Some(open @ (b'{' | b'[')) => {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("arrays and objects are nested too deeply"));
+                }
+                self.depth += 1;
+                let kind = if open == b'{' { self.object() } else { self.array() };
+                self.depth -= 1;
+                kind?
+            }
+            Some(b'"') => JsonKind::String(self.string()?),
+            Some(b't') => self.keyword("true", JsonKind::Bool(true))?,
+            Some(b'f') => self.keyword("false", JsonKind::Bool(false))?,
+            Some(b'n') => self.keyword("null", JsonKind::Null)?,
+            Some(b'-' | b'0'..=b'9') => self.number()?,
+            Some(_) => return Err(self.error("expected a JSON value")),
+            None => return Err(self.error("unexpected end of input")),
+        };
+        Ok(JsonValue {
+            kind,
+            span: start..self.pos,
+        })
+    }
+
+    fn keyword(&mut self, word: &str, kind: JsonKind) -> Result<JsonKind, JsonError> {
+        if self.text[self.pos..].starts_with(word) {
+            self.pos += word.len();
+            Ok(kind)
+        } else {
+            Err(self.error("expected a JSON value"))
+        }
+    }
+
+    /// `-?(0|[1-9][0-9]*)(.[0-9]+)?([eE][+-]?[0-9]+)?`
+    fn number(&mut self) -> Result<JsonKind, JsonError> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+        }
+        let integer = self.pos;
+        let mut valid = match self.digits() {
+            0 => false,
+            length => length == 1 || self.text.as_bytes()[integer] != b'0',
+        };
+        if valid && self.peek() == Some(b'.') {
+            self.pos += 1;
+            valid = self.digits() > 0;
+        }
+        if valid && matches!(self.peek(), Some(b'e' | b'E')) {
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'+' | b'-')) {
+                self.pos += 1;
+            }
+            valid = self.digits() > 0;
+        }
+        if !valid {
+            self.pos = start;
+            return Err(self.error("invalid number"));
+        }
+        Ok(JsonKind::Number)
+    }
+
+    /// Skip a run of decimal digits, returning how many there were
+    fn digits(&mut self) -> usize {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b'0'..=b'9')) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"')?;
+        let mut value = String::new();
+        loop {
+            let Some(ch) = self.text[self.pos..].chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.pos += ch.len_utf8();
+            match ch {
+                '"' => return Ok(value),
+                '\\' => value.push(self.escape()?),
+                c if (c as u32) < 0x20 => {
+                    self.pos -= 1;
+                    return Err(self.error("control character in string"));
+                }
+                c => value.push(c),
+            }
+        }
+    }
+
+    fn escape(&mut self) -> Result<char, JsonError> {
+        let escaped = self.peek().ok_or_else(|| self.error("unterminated string"))?;
+        self.pos += 1;
+        Ok(match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let code = self.hex4()?;
+                if (0xD800..0xDC00).contains(&code) && self.text[self.pos..].starts_with("\\u") {
+                    // Surrogate pair
+                    self.pos += 2;
+                    let low = self.hex4()?;
+                    let combined = 0x10000 + ((code - 0xD800) << 10) + (low.wrapping_sub(0xDC00) & 0x3FF);
+                    char::from_u32(combined).unwrap_or('\u{FFFD}')
+                } else {
+                    char::from_u32(code).unwrap_or('\u{FFFD}')
+                }
+            }
+            _ => {
+                self.pos -= 1;
+                return Err(self.error("invalid escape sequence"));
+            }
+        })
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        let code = u32::from_str_radix(digits, 16).map_err(|_| self.error("invalid unicode escape"))?;
+        self.pos += 4;
+        Ok(code)
+    }
+
+    fn array(&mut self) -> Result<JsonKind, JsonError> {
+        self.expect(b'[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            return Ok(JsonKind::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => {
+                    self.pos += 1;
+                    return Ok(JsonKind::Array(items));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<JsonKind, JsonError> {
+        self.expect(b'{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            return Ok(JsonKind::Object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key_start = self.pos;
+            if self.peek() != Some(b'"') {
+                return Err(self.error("expected a string key"));
+            }
+            let key = self.string()?;
+            let key_span = key_start..self.pos;
+            self.expect(b':')?;
+            let value = self.value()?;
+            members.push(JsonMember {
+                key,
+                key_span,
+                value,
+            });
+            self.skip_whitespace();
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return Ok(JsonKind::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_escapes() {
+        let value = parse(r#""a\"b\\c\/d\b\f\n\r\té""#).unwrap();
+        assert_eq!(value.as_str(), Some("a\"b\\c/d\u{8}\u{c}\n\r\té"));
+        assert!(parse(r#""\x""#).is_err());
+    }
+
+    #[test]
+    fn combines_surrogate_pairs() {
+        let value = parse(r#""\uD83D\uDE00!""#).unwrap();
+        assert_eq!(value.as_str(), Some("😀!"));
+    }
+
+    #[test]
+    fn spans_index_the_source() {
+        let text = r#"{"a": [1, "é", {"b": null}], "c": true}"#;
+        let root = parse(text).unwrap();
+        let members = root.as_object().unwrap();
+        assert_eq!(&text[members[0].key_span.clone()], r#""a""#);
+        assert_eq!(&text[members[0].value.span.clone()], r#"[1, "é", {"b": null}]"#);
+        let items = members[0].value.as_array().unwrap();
+        assert_eq!(&text[items[1].span.clone()], r#""é""#);
+        assert_eq!(&text[items[2].get("b").unwrap().span.clone()], "null");
+        assert_eq!(root.get("c").and_then(JsonValue::as_bool), Some(true));
+    }
+
+    #[test]
+    fn reports_where_parsing_failed() {
+        let error = parse(r#"{"a": 1,}"#).unwrap_err();
+        assert_eq!(error.offset, 8);
+        assert!(parse(r#"{"a": 1} 2"#).is_err());
+    }
+
+    #[test]
+    fn numbers_follow_the_json_grammar() {
+        for valid in ["0", "-0", "10", "1.5", "-0.25e-3", "2E+10"] {
+            assert!(parse(valid).is_ok(), "{}", valid);
+        }
+        for invalid in ["01", "-01", "1.", ".5", "1e", "-", "1.2.3", "+1"] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+        assert_eq!(parse("[1, 01]").unwrap_err().offset, 4);
+    }
+
+    #[test]
+    fn unicode_escapes_take_four_hex_digits() {
+        assert_eq!(parse(r#""\u0041""#).unwrap().as_str(), Some("A"));
+        for invalid in [r#""\u+041""#, r#""\u-041""#, r#""\u 041""#, r#""\u04""#] {
+            let error = parse(invalid).unwrap_err();
+            assert_eq!((error.message.as_str(), error.offset), ("invalid unicode escape", 3), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let nested = |depth| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        assert!(parse(&nested(MAX_DEPTH)).is_ok());
+        let error = parse(&nested(MAX_DEPTH + 1)).unwrap_err();
+        assert_eq!(error.message, "arrays and objects are nested too deeply");
+        assert_eq!(error.offset, MAX_DEPTH);
+        assert!(parse(&format!("{}1{}", "{\"a\":".repeat(100_000), "}".repeat(100_000))).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+//! The dependency lock file (`.terraform.lock.hcl`) and the provider source
+//! addresses it is keyed by.
+
+use hcl::edit::Span;
+use hcl::edit::expr::Expression;
+use hcl::edit::structure::Body;
+use std::ops::Range;
+use std::path::Path;
+
+use crate::utils::TerraformUtils;
+
+pub const LOCK_FILE_NAME: &str = ".terraform.lock.hcl";
+
+/// Registry host assumed when a provider source omits one
+const DEFAULT_REGISTRY: &str = "registry.terraform.io";
+
+/// One `provider "<address>" { ... }` block of the lock file
+pub struct LockEntry {
+    /// Span of the whole block
+    pub span: Range<usize>,
+    /// Normalized source address (`registry.terraform.io/hashicorp/aws`)
+    pub address: String,
+    /// Span of the address label, without quotes
+    pub address_span: Range<usize>,
+    pub version: Option<String>,
+    pub hashes: Vec<String>,
+}
+
+/// Provider selections recorded by `terraform init`
+pub struct LockFile {
+    pub entries: Vec<LockEntry>,
+}
+
+impl LockFile {
+    /// Whether a URI names a dependency lock file
+    pub fn is_lock_file(uri: &str) -> bool {
+        Path::new(uri)
+            .file_name()
+            .is_some_and(|name| name == LOCK_FILE_NAME)
+    }
+
+    pub fn from_body(body: &Body, text: &str) -> Self {
+        let entries = body
+            .blocks()
+            .filter(|block| block.has_ident("provider"))
+            .filter_map(|block| {
+                let label = block.labels.first()?;
+                let version = block.body.get_attribute("version").and_then(|attr| attr.value.as_str());
+                let hashes = match block.body.get_attribute("hashes").map(|attr| &attr.value) {
+                    Some(Expression::Array(items)) => items
+                        .iter()
+                        .filter_map(|item| item.as_str().map(str::to_string))
+                        .collect(),
+                    _ => Vec::new(),
+                };
+                Some(LockEntry {
+                    span: block.span().unwrap_or(0..0),
+                    address: normalize_source(label.as_str()),
+                    address_span: TerraformUtils::label_span(label, text),
+                    version: version.map(str::to_string),
+                    hashes,
+                })
+            })
+            .collect();
+        Self { entries }
+    }
+
+    pub fn entry(&self, address: &str) -> Option<&LockEntry> {
+        self.entries.iter().find(|entry| entry.address == address)
+    }
+}
+
+/// Expand a provider source to its fully qualified, lowercase address:
+/// `aws` and `hashicorp/aws` both become `registry.terraform.io/hashicorp/aws`
+pub fn normalize_source(source: &str) -> String {
+    let source = source.trim().to_lowercase();
+    match source.split('/').count() {
+        1 => format!("{}/hashicorp/{}", DEFAULT_REGISTRY, source),
+        2 => format!("{}/{}", DEFAULT_REGISTRY, source),
+        _ => source,
+    }
+}
+
+/// Built-in providers are part of Terraform itself and never locked
+pub fn is_builtin(address: &str) -> bool {
+    address.starts_with("terraform.io/builtin/")
+}
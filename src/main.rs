@@ -3,11 +3,35 @@ use forseti_sdk::core::{FileContext, PreprocessingContext, RulesetCapabilities};
 use forseti_sdk::ruleset::{Ruleset, RulesetOptions, RulesetServer};
 use serde_json::{json};
 use std::collections::HashMap;
+use std::sync::Arc;
 
+mod cache;
+mod diagnostic;
+mod json;
+mod lock_file;
+mod module;
+mod module_source;
+mod options;
+mod provider_schema;
+mod references;
 mod rules;
+mod secrets;
+mod state;
+mod suppression;
+mod terraform_json;
+#[cfg(test)]
+mod testing;
+mod types;
 mod utils;
+mod version;
 
+use cache::DocumentCache;
+use lock_file::LockFile;
+use module::Module;
+use options::TerraformRule;
 use rules::*;
+use suppression::SuppressibleRule;
+use utils::TerraformUtils;
 
 struct TerraformRuleset;
 
@@ -21,7 +45,15 @@ impl RulesetOptions for TerraformRuleset {
         RulesetCapabilities {
             ruleset_id: "terraform".to_string(),
             version: "0.1.0".to_string(),
-            file_patterns: vec!["*.tf".to_string(), "*.tfvars".to_string()],
+            file_patterns: vec![
+                "*.tf".to_string(),
+                "*.tfvars".to_string(),
+                "*.tf.json".to_string(),
+                "*.tfvars.json".to_string(),
+                lock_file::LOCK_FILE_NAME.to_string(),
+                "*.tfstate".to_string(),
+                "*.tfstate.backup".to_string(),
+            ],
             max_file_size: Some(5 * 1024 * 1024), // 5MB limit for Terraform files
             annotation_prefixes: vec![
                 "#".to_string(),  // HCL/Terraform single-line comments
@@ -29,17 +61,31 @@ impl RulesetOptions for TerraformRuleset {
             ],
             rules: vec![], // Will be populated by the server
             default_config: self.get_default_config(),
-            config_settings: vec![], // No custom settings for now, rule enable/disable will be auto-injected
+            // Per-rule options; rule enable/disable settings will be auto-injected
+            config_settings: terraform_rules(&Arc::new(DocumentCache::new()))
+                .iter()
+                .flat_map(|rule| {
+                    rule.option_specs()
+                        .into_iter()
+                        .map(|spec| spec.to_config_setting(rule.id()))
+                        .collect::<Vec<_>>()
+                })
+                .collect(),
         }
     }
 
     fn preprocess_files(&self, file_uris: &[String]) -> Result<PreprocessingContext> {
+        // Each run reports configuration errors afresh
+        options::clear_reported_config_errors();
+
         let mut files = Vec::new();
         let mut global_context = HashMap::new();
 
         // Terraform engine: gather Terraform-specific metadata
         let mut tf_files = 0;
         let mut tfvars_files = 0;
+        let mut state_files = 0;
+        let mut module_directories = std::collections::BTreeSet::new();
 
         for uri in file_uris {
             let mut context = HashMap::new();
@@ -58,23 +104,30 @@ impl RulesetOptions for TerraformRuleset {
                     let extension = ext.to_string_lossy();
                     context.insert("extension".to_string(), json!(extension));
 
-                    match extension.as_ref() {
-                        "tf" => {
-                            tf_files += 1;
-                            context
-                                .insert("terraform_file_type".to_string(), json!("configuration"));
-                        }
-                        "tfvars" => {
-                            tfvars_files += 1;
-                            context.insert("terraform_file_type".to_string(), json!("variables"));
-                        }
-                        _ => {}
+                    // JSON syntax files share the classification of their native counterparts
+                    if Module::is_configuration_file(path_obj) {
+                        tf_files += 1;
+                        context.insert("terraform_file_type".to_string(), json!("configuration"));
+                    } else if TerraformUtils::is_tfvars(path) {
+                        tfvars_files += 1;
+                        context.insert("terraform_file_type".to_string(), json!("variables"));
+                    } else if LockFile::is_lock_file(path) {
+                        context.insert("terraform_file_type".to_string(), json!("lock"));
+                    } else if TerraformUtils::is_state_file(path) {
+                        state_files += 1;
+                        context.insert("terraform_file_type".to_string(), json!("state"));
                     }
                 }
 
                 // Check if it's in a .terraform directory (should be ignored)
                 if path.contains("/.terraform/") {
                     context.insert("terraform_generated".to_string(), json!(true));
+                } else if let Some(directory) = Module::directory_of(uri) {
+                    // Terraform semantics are per directory, so every file knows its module
+                    context.insert("module".to_string(), json!(directory.display().to_string()));
+                    if Module::is_configuration_file(path_obj) {
+                        module_directories.insert(directory);
+                    }
                 }
             }
 
@@ -90,8 +143,21 @@ impl RulesetOptions for TerraformRuleset {
         global_context.insert("total_files".to_string(), json!(files.len()));
         global_context.insert("tf_files".to_string(), json!(tf_files));
         global_context.insert("tfvars_files".to_string(), json!(tfvars_files));
+        global_context.insert("state_files".to_string(), json!(state_files));
         global_context.insert("ruleset_type".to_string(), json!("terraform"));
 
+        // Module index: declarations of every module directory in the lint set
+        let documents = DocumentCache::new();
+        let modules: serde_json::Map<String, serde_json::Value> = module_directories
+            .iter()
+            .map(|directory| {
+                let name = directory.display().to_string();
+                let module = Module::load(directory, &documents);
+                (name, serde_json::to_value(&module.index).unwrap_or_default())
+            })
+            .collect();
+        global_context.insert("modules".to_string(), json!(modules));
+
         Ok(PreprocessingContext {
             ruleset_id: "terraform".to_string(),
             files,
@@ -101,13 +167,36 @@ impl RulesetOptions for TerraformRuleset {
 }
 
 fn create_terraform_ruleset() -> Ruleset {
-    Ruleset::new("terraform")
-        .with_rule(Box::new(NoHardcodedCredentialsRule))
-        .with_rule(Box::new(RequireProviderVersionRule))
-        .with_rule(Box::new(NoDeprecatedInterpolationRule))
-        .with_rule(Box::new(ResourceNamingConventionRule))
-        .with_rule(Box::new(VariableDescriptionRequiredRule))
-        .with_rule(Box::new(OutputDescriptionRequiredRule))
+    // One parse cache per ruleset so every HCL rule shares the same AST per file
+    let documents = Arc::new(DocumentCache::new());
+
+    terraform_rules(&documents)
+        .into_iter()
+        .fold(Ruleset::new("terraform"), |ruleset, rule| {
+            ruleset.with_rule(Box::new(SuppressibleRule::new(rule, documents.clone())))
+        })
+}
+
+fn terraform_rules(documents: &Arc<DocumentCache>) -> Vec<Box<dyn TerraformRule>> {
+    vec![
+        Box::new(NoHardcodedCredentialsRule::new(documents.clone())),
+        Box::new(RequireProviderVersionRule::new(documents.clone())),
+        Box::new(NoDeprecatedInterpolationRule::new(documents.clone())),
+        Box::new(ResourceNamingConventionRule::new(documents.clone())),
+        Box::new(VariableDescriptionRequiredRule::new(documents.clone())),
+        Box::new(OutputDescriptionRequiredRule::new(documents.clone())),
+        Box::new(HclSyntaxErrorRule::new(documents.clone())),
+        Box::new(NoUnusedVariablesRule::new(documents.clone())),
+        Box::new(NoUndefinedReferencesRule::new(documents.clone())),
+        Box::new(NoUnusedDeclarationsRule::new(documents.clone())),
+        Box::new(NoDuplicateDeclarationsRule::new(documents.clone())),
+        Box::new(ValidTfvarsRule::new(documents.clone())),
+        Box::new(ValidLockFileRule::new(documents.clone())),
+        Box::new(RequireModuleVersionRule::new(documents.clone())),
+        Box::new(RequireTerraformVersionRule::new(documents.clone())),
+        Box::new(ValidProviderSchemaRule::new(documents.clone())),
+        Box::new(NoLegacySyntaxRule::new(documents.clone())),
+    ]
 }
 
 fn infer_language(uri: &str) -> Option<String> {
@@ -117,6 +206,19 @@ fn infer_language(uri: &str) -> Option<String> {
         uri
     };
 
+    if path.ends_with(".tf.json") {
+        return Some("terraform-json".to_string());
+    }
+    if path.ends_with(".tfvars.json") {
+        return Some("terraform-vars-json".to_string());
+    }
+    if LockFile::is_lock_file(path) {
+        return Some("hcl".to_string());
+    }
+    if TerraformUtils::is_state_file(path) {
+        return Some("json".to_string());
+    }
+
     match std::path::Path::new(path)
         .extension()
         .and_then(|s| s.to_str())
@@ -131,3 +233,73 @@ fn main() -> Result<()> {
     let mut server = RulesetServer::new(Box::new(TerraformRuleset));
     server.run_stdio()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    /// Names of the declarations of one kind in a published module index
+    fn names<'a>(module: &'a Value, kind: &str) -> Vec<&'a str> {
+        module[kind]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|declaration| declaration["name"].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn module_index_is_published_per_directory() {
+        let root = std::env::temp_dir().join(format!("forseti-preprocess-{}", std::process::id()));
+        let child = root.join("modules/network");
+        std::fs::create_dir_all(&child).unwrap();
+        let files = [
+            (
+                root.join("main.tf"),
+                r#"variable "name" {}
+
+module "network" {
+  source = "./modules/network"
+}
+
+check "health" {
+  data "http" "site" {
+    url = "https://example.com"
+  }
+}
+"#,
+            ),
+            (root.join("outputs.tf"), "locals {\n  id = 1\n}\n\noutput \"id\" {\n  value = local.id\n}\n"),
+            (root.join("terraform.tfvars"), "name = \"web\"\n"),
+            (child.join("main.tf"), "resource \"aws_vpc\" \"main\" {}\n"),
+        ];
+        for (path, text) in &files {
+            std::fs::write(path, text).unwrap();
+        }
+        let uris: Vec<String> = files.iter().map(|(path, _)| format!("file://{}", path.display())).collect();
+
+        let context = {
+            let _run = testing::RUN.lock().unwrap_or_else(|e| e.into_inner());
+            TerraformRuleset.preprocess_files(&uris).unwrap()
+        };
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let modules = &context.global_context["modules"];
+        let directories: Vec<&String> = modules.as_object().unwrap().keys().collect();
+        assert_eq!(directories, [&root.display().to_string(), &child.display().to_string()]);
+
+        let module = &modules[root.display().to_string()];
+        assert_eq!(module["files"].as_array().unwrap().len(), 2);
+        assert_eq!(names(module, "variables"), ["name"]);
+        assert_eq!(names(module, "module_calls"), ["network"]);
+        assert_eq!(names(module, "data_sources"), ["http.site"]);
+        assert_eq!(names(module, "locals"), ["id"]);
+        assert_eq!(names(module, "outputs"), ["id"]);
+        assert_eq!(module["outputs"][0]["uri"], json!(uris[1]));
+
+        let module = &modules[child.display().to_string()];
+        assert_eq!(names(module, "resources"), ["aws_vpc.main"]);
+        assert!(names(module, "variables").is_empty());
+    }
+}
@@ -0,0 +1,250 @@
+use forseti_sdk::core::Range;
+use hcl::edit::Span;
+use hcl::edit::structure::{Block, BlockLabel, Body};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::cache::{DocumentCache, ParsedDocument};
+use crate::lock_file::LOCK_FILE_NAME;
+use crate::references::{self, Reference};
+use crate::utils::TerraformUtils;
+
+/// One configuration file of a module, parsed through the shared cache
+#[derive(Clone)]
+pub struct ModuleFile {
+    pub uri: String,
+    pub text: String,
+    pub document: Arc<ParsedDocument>,
+    /// Every reference made by the file's expressions
+    pub references: Vec<Reference>,
+}
+
+impl ModuleFile {
+    pub fn new(uri: String, text: String, document: Arc<ParsedDocument>) -> Self {
+        let references = document
+            .body
+            .as_ref()
+            .map(references::body_references)
+            .unwrap_or_default();
+        Self {
+            uri,
+            text,
+            document,
+            references,
+        }
+    }
+}
+
+/// A Terraform module: every configuration file in one directory
+pub struct Module {
+    pub files: Vec<ModuleFile>,
+    pub index: ModuleIndex,
+}
+
+impl Module {
+    /// Load the module in `directory` from disk
+    pub fn load(directory: &Path, documents: &DocumentCache) -> Self {
+        let files = Self::load_files(directory, documents);
+        Self::from_files(directory.display().to_string(), files)
+    }
+
+    pub fn from_files(directory: String, files: Vec<ModuleFile>) -> Self {
+        let index = ModuleIndex::build(directory, &files);
+        Self { files, index }
+    }
+
+    /// References made anywhere in the module
+    pub fn references(&self) -> impl Iterator<Item = &Reference> {
+        self.files.iter().flat_map(|file| &file.references)
+    }
+
+    /// Read and parse the configuration files of the module in `directory`
+    pub fn load_files(directory: &Path, documents: &DocumentCache) -> Vec<ModuleFile> {
+        Self::configuration_paths(directory)
+            .into_iter()
+            .filter_map(|path| {
+                let uri = format!("file://{}", path.display());
+                let text = std::fs::read_to_string(&path).ok()?;
+                let document = documents.get_or_parse(&uri, &text);
+                Some(ModuleFile::new(uri, text, document))
+            })
+            .collect()
+    }
+
+    /// Whether the module already holds `text` as the content of `uri`, or would not
+    /// include that file at all
+    pub fn has_content(&self, uri: &str, text: &str) -> bool {
+        match self.files.iter().find(|file| file.uri == uri) {
+            Some(file) => file.text == text,
+            None => !Self::is_configuration_file(Path::new(uri)),
+        }
+    }
+
+    /// This module with `text` in place of the content of `uri`, so unsaved editor
+    /// buffers are respected; the file is added when it is not on disk yet
+    pub fn with_overlay(&self, uri: &str, text: &str, documents: &DocumentCache) -> Self {
+        let overlay = ModuleFile::new(uri.to_string(), text.to_string(), documents.get_or_parse(uri, text));
+        let mut files = self.files.clone();
+        match files.iter_mut().find(|file| file.uri == uri) {
+            Some(file) => *file = overlay,
+            None => files.push(overlay),
+        }
+        Self::from_files(self.index.directory.clone(), files)
+    }
+
+    /// Configuration files Terraform would load from `directory`, in name order
+    pub fn configuration_paths(directory: &Path) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = std::fs::read_dir(directory)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.is_file() && Self::is_configuration_file(path))
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    /// `*.tf` files and their JSON equivalent `*.tf.json`
+    pub fn is_configuration_file(path: &Path) -> bool {
+        path.to_str()
+            .is_some_and(|p| p.ends_with(".tf") || p.ends_with(".tf.json"))
+    }
+
+    /// Whether this looks like a root module, one Terraform is run in directly rather
+    /// than called: it has been initialized (lock file or `.terraform` directory) or
+    /// configures a backend or HCP Terraform
+    pub fn is_root(&self) -> bool {
+        let directory = Path::new(&self.index.directory);
+        if !self.index.directory.is_empty()
+            && (directory.join(LOCK_FILE_NAME).is_file() || directory.join(".terraform").is_dir())
+        {
+            return true;
+        }
+        self.files
+            .iter()
+            .filter_map(|file| file.document.body.as_ref())
+            .flat_map(|body| body.blocks())
+            .filter(|block| block.has_ident("terraform"))
+            .flat_map(|block| block.body.blocks())
+            .any(|block| block.has_ident("backend") || block.has_ident("cloud"))
+    }
+
+    /// Directory of the module a `file://` URI belongs to
+    pub fn directory_of(uri: &str) -> Option<PathBuf> {
+        let path = Path::new(uri.strip_prefix("file://")?);
+        path.parent().map(Path::to_path_buf)
+    }
+}
+
+/// A named declaration and where it is defined
+#[derive(Debug, Clone, Serialize)]
+pub struct Declaration {
+    pub name: String,
+    pub uri: String,
+    pub range: Range,
+}
+
+/// Everything a module declares, across all of its files
+#[derive(Debug, Default, Serialize)]
+pub struct ModuleIndex {
+    pub directory: String,
+    pub files: Vec<String>,
+    pub variables: Vec<Declaration>,
+    pub locals: Vec<Declaration>,
+    pub outputs: Vec<Declaration>,
+    /// `<type>.<name>`
+    pub resources: Vec<Declaration>,
+    /// `<type>.<name>`, without the `data.` prefix
+    pub data_sources: Vec<Declaration>,
+    pub module_calls: Vec<Declaration>,
+    /// `<name>` or `<name>.<alias>` for aliased provider configurations
+    pub providers: Vec<Declaration>,
+}
+
+impl ModuleIndex {
+    /// Index the declarations of every file in a module
+    pub fn build(directory: String, files: &[ModuleFile]) -> Self {
+        let mut index = Self {
+            directory,
+            files: files.iter().map(|f| f.uri.clone()).collect(),
+            ..Self::default()
+        };
+
+        for file in files {
+            if let Some(body) = &file.document.body {
+                index.add_body(body, file);
+            }
+        }
+
+        index
+    }
+
+    fn add_body(&mut self, body: &Body, file: &ModuleFile) {
+        for block in body.blocks() {
+            match block.ident.as_str() {
+                "variable" => self.add_labeled(block, 1, file, |d| &mut d.variables),
+                "output" => self.add_labeled(block, 1, file, |d| &mut d.outputs),
+                "module" => self.add_labeled(block, 1, file, |d| &mut d.module_calls),
+                "resource" => self.add_labeled(block, 2, file, |d| &mut d.resources),
+                "data" => self.add_labeled(block, 2, file, |d| &mut d.data_sources),
+                "locals" => {
+                    for attr in block.body.attributes() {
+                        let span = attr.key.span().unwrap_or(0..0);
+                        self.locals
+                            .push(Self::declaration(attr.key.as_str().to_string(), span, file));
+                    }
+                }
+                "provider" => {
+                    let Some(label) = block.labels.first() else {
+                        continue;
+                    };
+                    let alias = block
+                        .body
+                        .get_attribute("alias")
+                        .and_then(|attr| attr.value.as_str());
+                    let name = match alias {
+                        Some(alias) => format!("{}.{}", label.as_str(), alias),
+                        None => label.as_str().to_string(),
+                    };
+                    let span = TerraformUtils::label_span(label, &file.text);
+                    self.providers.push(Self::declaration(name, span, file));
+                }
+                // Scoped data sources of `check` blocks
+                "check" => self.add_body(&block.body, file),
+                _ => {}
+            }
+        }
+    }
+
+    /// Record a block named by its first `label_count` labels joined with dots,
+    /// located at its last label
+    fn add_labeled(
+        &mut self,
+        block: &Block,
+        label_count: usize,
+        file: &ModuleFile,
+        target: fn(&mut Self) -> &mut Vec<Declaration>,
+    ) {
+        if block.labels.len() < label_count {
+            return;
+        }
+        let labels: &[BlockLabel] = &block.labels[..label_count];
+        let name = labels
+            .iter()
+            .map(BlockLabel::as_str)
+            .collect::<Vec<_>>()
+            .join(".");
+        let span = TerraformUtils::label_span(&labels[label_count - 1], &file.text);
+        let declaration = Self::declaration(name, span, file);
+        target(self).push(declaration);
+    }
+
+    fn declaration(name: String, span: std::ops::Range<usize>, file: &ModuleFile) -> Declaration {
+        Declaration {
+            range: TerraformUtils::span_to_range(&span, &file.text),
+            name,
+            uri: file.uri.clone(),
+        }
+    }
+}
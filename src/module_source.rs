@@ -0,0 +1,129 @@
+//! Classification of module `source` addresses, following the rules Terraform
+//! (through go-getter) uses to pick an installer for a module.
+
+use std::fmt;
+
+/// Where a module is installed from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceKind {
+    /// `./` or `../` path inside the same repository
+    Local,
+    /// `[<host>/]<namespace>/<name>/<provider>` from a module registry
+    Registry,
+    Git,
+    Mercurial,
+    /// Archive downloaded over HTTP(S)
+    Http,
+    S3,
+    Gcs,
+    Unknown,
+}
+
+impl SourceKind {
+    /// Sources that track a repository and therefore need a ref to be reproducible
+    pub fn is_vcs(self) -> bool {
+        matches!(self, SourceKind::Git | SourceKind::Mercurial)
+    }
+}
+
+impl fmt::Display for SourceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            SourceKind::Local => "local path",
+            SourceKind::Registry => "registry",
+            SourceKind::Git => "git",
+            SourceKind::Mercurial => "mercurial",
+            SourceKind::Http => "http",
+            SourceKind::S3 => "s3",
+            SourceKind::Gcs => "gcs",
+            SourceKind::Unknown => "unknown",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A parsed module source address
+pub struct ModuleSource<'a> {
+    pub kind: SourceKind,
+    raw: &'a str,
+}
+
+impl<'a> ModuleSource<'a> {
+    pub fn parse(source: &'a str) -> Self {
+        Self {
+            kind: Self::classify(source.trim()),
+            raw: source,
+        }
+    }
+
+    fn classify(source: &str) -> SourceKind {
+        if source.starts_with("./") || source.starts_with("../") {
+            return SourceKind::Local;
+        }
+
+        // Forced installers: `git::https://...`, `s3::https://...`
+        const FORCED: [(&str, SourceKind); 4] = [
+            ("git::", SourceKind::Git),
+            ("hg::", SourceKind::Mercurial),
+            ("s3::", SourceKind::S3),
+            ("gcs::", SourceKind::Gcs),
+        ];
+        if let Some((_, kind)) = FORCED.iter().find(|(prefix, _)| source.starts_with(prefix)) {
+            return *kind;
+        }
+
+        if source.starts_with("git@")
+            || source.starts_with("github.com/")
+            || source.starts_with("bitbucket.org/")
+        {
+            return SourceKind::Git;
+        }
+
+        let location = source
+            .strip_prefix("https://")
+            .or_else(|| source.strip_prefix("http://"));
+        let host = location.unwrap_or(source).split('/').next().unwrap_or_default();
+        if host.ends_with(".amazonaws.com") {
+            return SourceKind::S3;
+        }
+        if host == "www.googleapis.com" {
+            return SourceKind::Gcs;
+        }
+        if location.is_some() {
+            return SourceKind::Http;
+        }
+
+        if Self::is_registry_address(source) {
+            SourceKind::Registry
+        } else {
+            SourceKind::Unknown
+        }
+    }
+
+    /// `<namespace>/<name>/<provider>`, optionally prefixed by a hostname and
+    /// followed by a `//subdirectory`
+    fn is_registry_address(source: &str) -> bool {
+        let address = source.split_once("//").map_or(source, |(address, _)| address);
+        let parts: Vec<&str> = address.split('/').collect();
+        let is_segment = |part: &&str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        match parts.as_slice() {
+            [_, _, _] => parts.iter().all(is_segment),
+            [host, rest @ ..] if rest.len() == 3 => host.contains('.') && rest.iter().all(is_segment),
+            _ => false,
+        }
+    }
+
+    /// The revision selected with `?ref=` (or Mercurial's `?rev=`)
+    pub fn vcs_ref(&self) -> Option<&'a str> {
+        let (_, query) = self.raw.split_once('?')?;
+        query.split('&').find_map(|pair| match pair.split_once('=') {
+            Some(("ref" | "rev", value)) if !value.is_empty() => Some(value),
+            _ => None,
+        })
+    }
+}
@@ -0,0 +1,362 @@
+use forseti_sdk::core::{ConfigSetting, ConfigType};
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use crate::diagnostic::{DiagnosticBuilder, SEVERITIES, configured_severity};
+
+/// Configuration errors already reported in this run, by rule id and message, with
+/// the file each was reported on
+static REPORTED_CONFIG_ERRORS: LazyLock<Mutex<HashMap<(String, String), String>>> =
+    LazyLock::new(Default::default);
+
+/// Start a new run, in which every configuration error is reported again
+pub fn clear_reported_config_errors() {
+    REPORTED_CONFIG_ERRORS.lock().unwrap_or_else(|e| e.into_inner()).clear();
+}
+
+/// A rule in the Terraform ruleset, with the options it understands.
+///
+/// Rule configs follow the `"warn"` / `["warn", { ...options }]` convention; the
+/// option object is validated against `option_specs` before the rule runs.
+pub trait TerraformRule: Rule {
+    /// Options this rule reads from the second element of its config
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        Vec::new()
+    }
+
+    /// Whether the rule should run, reporting a severity that is not recognised and
+    /// unknown or invalid options (the rule then runs with their defaults)
+    fn enabled(&self, ctx: &mut RuleContext) -> bool {
+        let severity = match ctx.options {
+            Value::Array(items) => items.first().unwrap_or(&Value::Null),
+            other => other,
+        };
+        if !severity.as_str().is_some_and(|s| SEVERITIES.contains(&s)) {
+            let error = format!("severity must be one of {}, got {}", SEVERITIES.join(", "), severity);
+            self.report_invalid_config(ctx, &error);
+            return false;
+        }
+        if configured_severity(ctx.options).is_none() {
+            return false;
+        }
+
+        let (_, errors) = RuleOptions::resolve(ctx.options, &self.option_specs());
+        for error in errors {
+            self.report_invalid_config(ctx, &error);
+        }
+        true
+    }
+
+    /// Resolve this rule's options for the current check; invalid values are
+    /// reported by [`TerraformRule::enabled`] and replaced by their defaults
+    fn options(&self, ctx: &mut RuleContext) -> RuleOptions {
+        RuleOptions::resolve(ctx.options, &self.option_specs()).0
+    }
+
+    /// Configuration mistakes are always errors, whatever the rule's own severity.
+    /// Each one is reported on the first file it is found in rather than on every file.
+    fn report_invalid_config(&self, ctx: &mut RuleContext, error: &str) {
+        let mut reported = REPORTED_CONFIG_ERRORS.lock().unwrap_or_else(|e| e.into_inner());
+        let first_uri = reported
+            .entry((self.id().to_string(), error.to_string()))
+            .or_insert_with(|| ctx.uri.to_string());
+        if first_uri != ctx.uri {
+            return;
+        }
+        drop(reported);
+
+        let diagnostic = DiagnosticBuilder::new(
+            self.id(),
+            format!("Invalid configuration for rule '{}': {}", self.id(), error),
+        )
+        .with_code("INVALID_OPTIONS")
+        .build("error");
+        ctx.report(diagnostic);
+    }
+}
+
+/// Extra validation for an option value beyond its declared type
+pub type OptionValidator = fn(&Value) -> Result<(), String>;
+
+/// Declaration of a single rule option: its type, default and constraints
+pub struct OptionSpec {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub setting_type: ConfigType,
+    pub default: Value,
+    pub allowed_values: Option<Vec<Value>>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub validator: Option<OptionValidator>,
+}
+
+impl OptionSpec {
+    pub fn new(
+        name: &'static str,
+        description: &'static str,
+        setting_type: ConfigType,
+        default: Value,
+    ) -> Self {
+        Self {
+            name,
+            description,
+            setting_type,
+            default,
+            allowed_values: None,
+            min: None,
+            max: None,
+            validator: None,
+        }
+    }
+
+    pub fn with_allowed_values(mut self, values: Vec<Value>) -> Self {
+        self.allowed_values = Some(values);
+        self
+    }
+
+    /// Bounds for a numeric option, either of which may be left open
+    pub fn with_range(mut self, min: Option<f64>, max: Option<f64>) -> Self {
+        self.min = min;
+        self.max = max;
+        self
+    }
+
+    pub fn with_validator(mut self, validator: OptionValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    /// Describe this option as a ruleset config setting named `<rule-id>.<option>`
+    pub fn to_config_setting(&self, rule_id: &str) -> ConfigSetting {
+        ConfigSetting {
+            name: format!("{}.{}", rule_id, self.name),
+            description: self.description.to_string(),
+            setting_type: self.setting_type.clone(),
+            default: self.default.clone(),
+            required: false,
+            allowed_values: self.allowed_values.clone(),
+            min: self.min,
+            max: self.max,
+        }
+    }
+
+    /// Check a configured value against the declared type and constraints
+    fn validate(&self, value: &Value) -> Result<(), String> {
+        let type_ok = match self.setting_type {
+            ConfigType::String => value.is_string(),
+            ConfigType::Number => value.is_number(),
+            ConfigType::Integer => value.is_i64() || value.is_u64(),
+            ConfigType::Boolean => value.is_boolean(),
+            ConfigType::Array => value.is_array(),
+            ConfigType::Object => value.is_object(),
+            ConfigType::Enum => true,
+        };
+        if !type_ok {
+            return Err(format!(
+                "option '{}' must be of type {}",
+                self.name,
+                serde_json::to_string(&self.setting_type).unwrap_or_default().trim_matches('"')
+            ));
+        }
+
+        if let Some(allowed) = &self.allowed_values
+            && !allowed.contains(value)
+        {
+            return Err(format!(
+                "option '{}' must be one of {}",
+                self.name,
+                Value::Array(allowed.clone())
+            ));
+        }
+
+        if let Some(number) = value.as_f64() {
+            if self.min.is_some_and(|min| number < min) {
+                return Err(format!("option '{}' must be >= {}", self.name, self.min.unwrap()));
+            }
+            if self.max.is_some_and(|max| number > max) {
+                return Err(format!("option '{}' must be <= {}", self.name, self.max.unwrap()));
+            }
+        }
+
+        if let Some(validator) = self.validator {
+            validator(value).map_err(|e| format!("option '{}': {}", self.name, e))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolved option values for one rule, with defaults filled in
+#[derive(Debug, Default)]
+pub struct RuleOptions {
+    values: Map<String, Value>,
+}
+
+impl RuleOptions {
+    /// Resolve a rule config (`"warn"` or `["warn", { ... }]`) against option specs.
+    ///
+    /// Unknown options and invalid values are returned as errors; invalid values fall
+    /// back to their defaults so the rule still runs.
+    pub fn resolve(config: &Value, specs: &[OptionSpec]) -> (Self, Vec<String>) {
+        let mut values = Map::new();
+        let mut errors = Vec::new();
+
+        let configured = match config.as_array().and_then(|a| a.get(1)) {
+            Some(Value::Object(map)) => Some(map),
+            Some(other) => {
+                errors.push(format!("options must be an object, got {}", other));
+                None
+            }
+            None => None,
+        };
+
+        if let Some(configured) = configured {
+            for key in configured.keys() {
+                if !specs.iter().any(|spec| spec.name == key) {
+                    errors.push(format!("unknown option '{}'", key));
+                }
+            }
+        }
+
+        for spec in specs {
+            let value = match configured.and_then(|c| c.get(spec.name)) {
+                Some(value) => match spec.validate(value) {
+                    Ok(()) => value.clone(),
+                    Err(error) => {
+                        errors.push(error);
+                        spec.default.clone()
+                    }
+                },
+                None => spec.default.clone(),
+            };
+            values.insert(spec.name.to_string(), value);
+        }
+
+        (Self { values }, errors)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Value> {
+        self.values.get(name)
+    }
+
+    pub fn str(&self, name: &str) -> &str {
+        self.get(name).and_then(Value::as_str).unwrap_or_default()
+    }
+
+    pub fn bool(&self, name: &str) -> bool {
+        self.get(name).and_then(Value::as_bool).unwrap_or_default()
+    }
+
+    pub fn string_list(&self, name: &str) -> Vec<String> {
+        self.get(name)
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Validator for options holding a single regular expression
+pub fn validate_regex(value: &Value) -> Result<(), String> {
+    let pattern = value.as_str().unwrap_or_default();
+    regex::Regex::new(pattern)
+        .map(|_| ())
+        .map_err(|e| format!("invalid regex '{}': {}", pattern, e))
+}
+
+/// Validator for options holding a list of regular expressions
+pub fn validate_regex_list(value: &Value) -> Result<(), String> {
+    validate_string_list(value)?;
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .try_for_each(validate_regex)
+}
+
+/// Validator for options holding a list of strings
+pub fn validate_string_list(value: &Value) -> Result<(), String> {
+    match value.as_array() {
+        Some(items) if items.iter().all(Value::is_string) => Ok(()),
+        _ => Err("expected an array of strings".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+    use serde_json::json;
+
+    /// A rule with one option that reports nothing itself
+    struct OptionsRule;
+
+    impl Rule for OptionsRule {
+        fn id(&self) -> &'static str {
+            "test-options"
+        }
+
+        fn description(&self) -> &'static str {
+            "Reports configuration errors only"
+        }
+
+        fn default_config(&self) -> Value {
+            json!("warn")
+        }
+
+        fn check(&self, ctx: &mut RuleContext) {
+            self.enabled(ctx);
+        }
+    }
+
+    impl TerraformRule for OptionsRule {
+        fn option_specs(&self) -> Vec<OptionSpec> {
+            vec![
+                OptionSpec::new("limit", "A number", ConfigType::Integer, json!(1))
+                    .with_range(Some(1.0), Some(10.0)),
+            ]
+        }
+    }
+
+    #[test]
+    fn invalid_options_are_reported_once_per_run_without_reading_them() {
+        let _run = crate::testing::RUN.lock().unwrap_or_else(|e| e.into_inner());
+        let config = json!(["warn", { "limt": 2, "limit": "2" }]);
+        let first = run(&OptionsRule, "file:///a.tf", "", config.clone());
+        assert_eq!(codes(&first), ["INVALID_OPTIONS", "INVALID_OPTIONS"]);
+        assert!(first[0].message.contains("unknown option 'limt'"));
+        assert!(first[1].message.contains("option 'limit' must be of type integer"));
+
+        assert!(run(&OptionsRule, "file:///b.tf", "", config.clone()).is_empty());
+        // Checking the first file again still shows them
+        assert_eq!(run(&OptionsRule, "file:///a.tf", "", config.clone()).len(), 2);
+
+        clear_reported_config_errors();
+        assert_eq!(run(&OptionsRule, "file:///b.tf", "", config).len(), 2);
+    }
+
+    #[test]
+    fn numbers_must_be_in_range() {
+        let specs = OptionsRule.option_specs();
+        let (options, errors) = RuleOptions::resolve(&json!(["warn", { "limit": 11 }]), &specs);
+        assert_eq!(options.get("limit"), Some(&json!(1)));
+        assert_eq!(errors, ["option 'limit' must be <= 10"]);
+        assert!(RuleOptions::resolve(&json!(["warn", { "limit": 10 }]), &specs).1.is_empty());
+        assert_eq!(specs[0].to_config_setting("test-options").min, Some(1.0));
+    }
+
+    #[test]
+    fn defaults_replace_invalid_values() {
+        let specs = OptionsRule.option_specs();
+        let (options, errors) = RuleOptions::resolve(&json!(["warn", { "limit": "x" }]), &specs);
+        assert_eq!(options.get("limit"), Some(&json!(1)));
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,126 @@
+//! Provider schemas as exported by `terraform providers schema -json`, used to
+//! validate resource bodies without network access.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Top level of the `terraform providers schema -json` output
+#[derive(Debug, Default, Deserialize)]
+pub struct ProviderSchemas {
+    #[serde(default)]
+    pub provider_schemas: BTreeMap<String, ProviderSchema>,
+}
+
+/// Schemas of everything one provider offers, keyed by type name
+#[derive(Debug, Default, Deserialize)]
+pub struct ProviderSchema {
+    #[serde(default)]
+    pub resource_schemas: BTreeMap<String, Schema>,
+    #[serde(default)]
+    pub data_source_schemas: BTreeMap<String, Schema>,
+    #[serde(default)]
+    pub ephemeral_resource_schemas: BTreeMap<String, Schema>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Schema {
+    pub block: BlockSchema,
+}
+
+/// Arguments and nested blocks allowed in a block body
+#[derive(Debug, Default, Deserialize)]
+pub struct BlockSchema {
+    #[serde(default)]
+    pub attributes: BTreeMap<String, AttributeSchema>,
+    #[serde(default)]
+    pub block_types: BTreeMap<String, NestedBlockSchema>,
+    #[serde(default)]
+    pub deprecated: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct AttributeSchema {
+    #[serde(default)]
+    pub required: bool,
+    #[serde(default)]
+    pub optional: bool,
+    #[serde(default)]
+    pub computed: bool,
+    #[serde(default)]
+    pub deprecated: bool,
+}
+
+impl AttributeSchema {
+    /// Computed-only attributes are exported by the provider but cannot be set
+    pub fn is_read_only(&self) -> bool {
+        self.computed && !self.optional && !self.required
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct NestedBlockSchema {
+    pub block: BlockSchema,
+    #[serde(default)]
+    pub min_items: u64,
+}
+
+impl ProviderSchemas {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read provider schema file '{}': {}", path.display(), e))?;
+        serde_json::from_str(&text)
+            .map_err(|e| format!("invalid provider schema file '{}': {}", path.display(), e))
+    }
+
+    /// Schemas of one kind of block (`resource`, `data` or `ephemeral`) across all providers
+    pub fn types(&self, block_type: &str) -> impl Iterator<Item = (&String, &Schema)> {
+        self.provider_schemas
+            .values()
+            .flat_map(move |provider| match block_type {
+                "resource" => &provider.resource_schemas,
+                "data" => &provider.data_source_schemas,
+                _ => &provider.ephemeral_resource_schemas,
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn types_are_listed_by_block_kind() {
+        let schemas: ProviderSchemas = serde_json::from_str(
+            r#"{
+  "format_version": "1.0",
+  "provider_schemas": {
+    "registry.terraform.io/hashicorp/aws": {
+      "provider": { "block": {} },
+      "resource_schemas": {
+        "aws_instance": { "block": { "attributes": { "arn": { "type": "string", "computed": true } } } }
+      },
+      "data_source_schemas": { "aws_ami": { "block": {} } }
+    }
+  }
+}"#,
+        )
+        .unwrap();
+
+        let names = |kind| schemas.types(kind).map(|(name, _)| name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names("resource"), ["aws_instance"]);
+        assert_eq!(names("data"), ["aws_ami"]);
+        assert!(names("ephemeral").is_empty());
+
+        let (_, instance) = schemas.types("resource").next().unwrap();
+        assert!(instance.block.attributes["arn"].is_read_only());
+    }
+
+    #[test]
+    fn load_errors_name_the_file() {
+        let path = std::env::temp_dir().join(format!("forseti-schema-missing-{}.json", std::process::id()));
+        let error = ProviderSchemas::load(&path).unwrap_err();
+        assert!(error.starts_with("cannot read provider schema file"));
+        assert!(error.contains(&path.display().to_string()));
+    }
+}
@@ -0,0 +1,191 @@
+use hcl::edit::Span;
+use hcl::edit::expr::{Expression, ForExpr, TraversalOperator};
+use hcl::edit::structure::{Attribute, Block, Body};
+use hcl::edit::template::ForDirective;
+use hcl::edit::visit::{self, Visit};
+use hcl::edit::{Decorated, Ident};
+
+/// A reference such as `var.name` or `aws_instance.web.id` found in an expression
+#[derive(Debug, Clone)]
+pub struct Reference {
+    /// First identifier of the traversal (`var`, `local`, `aws_instance`, ...)
+    pub root: String,
+    /// Attribute names following the root, up to the first index or splat
+    pub attributes: Vec<String>,
+    /// Byte spans of the root followed by each attribute name
+    spans: Vec<std::ops::Range<usize>>,
+}
+
+impl Reference {
+    /// The `n`th attribute after the root, e.g. the variable name of `var.name`
+    pub fn attribute(&self, n: usize) -> Option<&str> {
+        self.attributes.get(n).map(String::as_str)
+    }
+
+    /// Span from the root through the `n`th attribute, e.g. `var.name` of `var.name.id`
+    pub fn span_through(&self, n: usize) -> std::ops::Range<usize> {
+        let start = self.spans.first().map_or(0, |s| s.start);
+        let end = self.spans.get(n + 1).or(self.spans.last()).map_or(start, |s| s.end);
+        start..end
+    }
+}
+
+/// Attributes whose values name providers, attributes or past addresses rather
+/// than referring to values, so they are not collected
+const NON_REFERENCE_ATTRIBUTES: &[&str] = &["provider", "providers", "ignore_changes"];
+
+/// Collect every reference in a body, including those inside string templates,
+/// heredocs, `for` expressions and `dynamic` blocks.
+///
+/// Names bound locally by `for` expressions, template `for` directives and
+/// `dynamic` block iterators are not reported as references, nor are meta-arguments
+/// such as `provider`, `ignore_changes` and the `from` address of `moved` blocks.
+pub fn body_references(body: &Body) -> Vec<Reference> {
+    let mut collector = ReferenceCollector::default();
+    collector.visit_body(body);
+    collector.references
+}
+
+/// Collect every reference in a single block, see [`body_references`]
+pub fn block_references(block: &Block) -> Vec<Reference> {
+    let mut collector = ReferenceCollector::default();
+    collector.visit_block(block);
+    collector.references
+}
+
+/// Collect every reference in a single expression
+pub fn expression_references(expr: &Expression) -> Vec<Reference> {
+    let mut collector = ReferenceCollector::default();
+    collector.visit_expr(expr);
+    collector.references
+}
+
+#[derive(Default)]
+struct ReferenceCollector {
+    references: Vec<Reference>,
+    /// Names bound by enclosing `for` expressions and `dynamic` blocks
+    bound: Vec<String>,
+}
+
+impl ReferenceCollector {
+    fn record(&mut self, root: &Decorated<Ident>, operators: &[&TraversalOperator]) {
+        if self.bound.iter().any(|name| name == root.as_str()) {
+            return;
+        }
+        let mut attributes = Vec::new();
+        let mut spans = vec![root.span().unwrap_or(0..0)];
+        for operator in operators {
+            let TraversalOperator::GetAttr(ident) = operator else {
+                break;
+            };
+            attributes.push(ident.as_str().to_string());
+            spans.push(ident.span().unwrap_or(0..0));
+        }
+        self.references.push(Reference {
+            root: root.as_str().to_string(),
+            attributes,
+            spans,
+        });
+    }
+
+    /// Visit `f` with `names` bound, restoring the previous bindings afterwards
+    fn with_bound(&mut self, names: Vec<String>, f: impl FnOnce(&mut Self)) {
+        let depth = self.bound.len();
+        self.bound.extend(names);
+        f(self);
+        self.bound.truncate(depth);
+    }
+}
+
+impl Visit for ReferenceCollector {
+    fn visit_expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Variable(ident) => self.record(ident, &[]),
+            Expression::Traversal(traversal) => {
+                if let Expression::Variable(root) = &traversal.expr {
+                    let operators: Vec<&TraversalOperator> =
+                        traversal.operators.iter().map(|op| op.value()).collect();
+                    self.record(root, &operators);
+                } else {
+                    self.visit_expr(&traversal.expr);
+                }
+                for operator in &traversal.operators {
+                    self.visit_traversal_operator(operator);
+                }
+            }
+            _ => visit::visit_expr(self, expr),
+        }
+    }
+
+    fn visit_for_expr(&mut self, node: &ForExpr) {
+        // The collection is evaluated outside the loop's scope
+        self.visit_expr(&node.intro.collection_expr);
+
+        let mut names = vec![node.intro.value_var.as_str().to_string()];
+        names.extend(node.intro.key_var.iter().map(|k| k.as_str().to_string()));
+        self.with_bound(names, |this| {
+            if let Some(key_expr) = &node.key_expr {
+                this.visit_expr(key_expr);
+            }
+            this.visit_expr(&node.value_expr);
+            if let Some(cond) = &node.cond {
+                this.visit_expr(&cond.expr);
+            }
+        });
+    }
+
+    fn visit_for_directive(&mut self, node: &ForDirective) {
+        let for_expr = &node.for_expr;
+        self.visit_expr(&for_expr.collection_expr);
+
+        let mut names = vec![for_expr.value_var.as_str().to_string()];
+        names.extend(for_expr.key_var.iter().map(|k| k.as_str().to_string()));
+        self.with_bound(names, |this| this.visit_template(&for_expr.template));
+    }
+
+    fn visit_attr(&mut self, attr: &Attribute) {
+        if !NON_REFERENCE_ATTRIBUTES.contains(&attr.key.as_str()) {
+            visit::visit_attr(self, attr);
+        }
+    }
+
+    fn visit_block(&mut self, block: &Block) {
+        if block.has_ident("moved") || block.has_ident("removed") {
+            // `from` names an address that no longer exists
+            for attr in block.body.attributes() {
+                if attr.key.as_str() != "from" {
+                    self.visit_attr(attr);
+                }
+            }
+            return;
+        }
+        if !block.has_ident("dynamic") {
+            visit::visit_block(self, block);
+            return;
+        }
+
+        // dynamic "name" { for_each = ..., iterator = it, content { it.value } }
+        let iterator = block
+            .body
+            .get_attribute("iterator")
+            .and_then(|attr| match &attr.value {
+                Expression::Variable(ident) => Some(ident.as_str().to_string()),
+                _ => None,
+            })
+            .or_else(|| block.labels.first().map(|label| label.as_str().to_string()));
+
+        for attr in block.body.attributes() {
+            if attr.key.as_str() == "for_each" {
+                self.visit_expr(&attr.value);
+            }
+        }
+        self.with_bound(iterator.into_iter().collect(), |this| {
+            for structure in block.body.iter() {
+                match structure.as_attribute() {
+                    Some(attr) if matches!(attr.key.as_str(), "for_each" | "iterator") => {}
+                    _ => this.visit_structure(structure),
+                }
+            }
+        });
+    }
+}
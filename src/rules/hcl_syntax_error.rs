@@ -0,0 +1,114 @@
+use forseti_sdk::core::LineIndex;
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::options::TerraformRule;
+use crate::utils::TerraformUtils;
+use std::sync::Arc;
+
+pub struct HclSyntaxErrorRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl HclSyntaxErrorRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
+
+impl Rule for HclSyntaxErrorRule {
+    fn id(&self) -> &'static str {
+        "hcl-syntax-error"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports Terraform files that cannot be parsed as HCL instead of silently skipping them"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("error".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        if !self.enabled(ctx) {
+            return;
+        }
+
+        let document = self.documents.get_or_parse(ctx.uri, ctx.text);
+        let Some(error) = &document.error else {
+            return;
+        };
+
+        // Highlight the single character the parser stopped at (empty at end of file)
+        let start = error.offset.min(ctx.text.len());
+        let end = ctx.text[start..]
+            .chars()
+            .next()
+            .map_or(start, |ch| start + ch.len_utf8());
+        let line_index = LineIndex::new(ctx.text);
+
+        let syntax = if TerraformUtils::is_json_syntax(ctx.uri) { "JSON" } else { "HCL" };
+        DiagnosticBuilder::new(self.id(), format!("Invalid {} syntax: {}", syntax, error.message))
+            .with_range(line_index.to_range(start, end))
+            .with_code("SYNTAX_ERROR")
+            .report(ctx);
+    }
+}
+
+impl TerraformRule for HclSyntaxErrorRule {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{RequireProviderVersionRule, ResourceNamingConventionRule, VariableDescriptionRequiredRule};
+    use crate::testing::{codes, run};
+    use forseti_sdk::ruleset::Rule;
+    use serde_json::json;
+
+    /// Something for every rule below to report, if the file could be parsed
+    const UNTERMINATED: &str = r#"variable "name" {}
+
+terraform {
+  required_providers {
+    aws = { source = "hashicorp/aws" }
+  }
+}
+
+resource "aws_instance" "WebServer" {
+  ami = "ami-123"
+"#;
+
+    #[test]
+    fn syntax_errors_are_reported_once_where_the_parser_stopped() {
+        let documents = Arc::new(DocumentCache::new());
+        let offset = documents
+            .get_or_parse("file:///main.tf", UNTERMINATED)
+            .error
+            .as_ref()
+            .map(|error| error.offset)
+            .unwrap();
+
+        let diagnostics = run(&HclSyntaxErrorRule::new(documents), "file:///main.tf", UNTERMINATED, json!("error"));
+        assert_eq!(codes(&diagnostics), ["SYNTAX_ERROR"]);
+        assert!(diagnostics[0].message.starts_with("Invalid HCL syntax: "));
+        let position = LineIndex::new(UNTERMINATED).to_pos(offset);
+        let start = &diagnostics[0].range.start;
+        assert_eq!((start.line, start.character), (position.line, position.character));
+    }
+
+    #[test]
+    fn other_rules_skip_files_that_do_not_parse() {
+        let documents = Arc::new(DocumentCache::new());
+        let rules: Vec<Box<dyn Rule>> = vec![
+            Box::new(VariableDescriptionRequiredRule::new(Arc::clone(&documents))),
+            Box::new(RequireProviderVersionRule::new(Arc::clone(&documents))),
+            Box::new(ResourceNamingConventionRule::new(Arc::clone(&documents))),
+        ];
+        for rule in &rules {
+            assert!(run(rule.as_ref(), "file:///main.tf", UNTERMINATED, json!("error")).is_empty(), "{}", rule.id());
+            // ...which they would otherwise report on
+            let closed = format!("{}}}\n", UNTERMINATED);
+            assert!(!run(rule.as_ref(), "file:///main.tf", &closed, json!("error")).is_empty(), "{}", rule.id());
+        }
+    }
+}
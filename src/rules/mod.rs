@@ -4,10 +4,32 @@ mod no_deprecated_interpolation;
 mod resource_naming_convention;
 mod variable_description_required;
 mod output_description_required;
+mod hcl_syntax_error;
+mod no_unused_variables;
+mod no_undefined_references;
+mod no_unused_declarations;
+mod no_duplicate_declarations;
+mod valid_tfvars;
+mod valid_lock_file;
+mod require_module_version;
+mod require_terraform_version;
+mod valid_provider_schema;
+mod no_legacy_syntax;
 
 pub use no_hardcoded_credentials::NoHardcodedCredentialsRule;
 pub use require_provider_version::RequireProviderVersionRule;
 pub use no_deprecated_interpolation::NoDeprecatedInterpolationRule;
 pub use resource_naming_convention::ResourceNamingConventionRule;
 pub use variable_description_required::VariableDescriptionRequiredRule;
-pub use output_description_required::OutputDescriptionRequiredRule;
\ No newline at end of file
+pub use output_description_required::OutputDescriptionRequiredRule;
+pub use hcl_syntax_error::HclSyntaxErrorRule;
+pub use no_unused_variables::NoUnusedVariablesRule;
+pub use no_undefined_references::NoUndefinedReferencesRule;
+pub use no_unused_declarations::NoUnusedDeclarationsRule;
+pub use no_duplicate_declarations::NoDuplicateDeclarationsRule;
+pub use valid_tfvars::ValidTfvarsRule;
+pub use valid_lock_file::ValidLockFileRule;
+pub use require_module_version::RequireModuleVersionRule;
+pub use require_terraform_version::RequireTerraformVersionRule;
+pub use valid_provider_schema::ValidProviderSchemaRule;
+pub use no_legacy_syntax::NoLegacySyntaxRule;
\ No newline at end of file
@@ -1,8 +1,49 @@
-use forseti_sdk::core::{Diagnostic, LineIndex, Range};
+use forseti_sdk::core::{Fix, SuggestFix};
 use forseti_sdk::ruleset::{Rule, RuleContext};
-use regex::Regex;
+use hcl::edit::Span;
+use hcl::edit::expr::Expression;
+use hcl::edit::structure::Body;
+use hcl::edit::template::Element;
+use hcl::edit::visit::{self, Visit};
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::options::TerraformRule;
+use crate::utils::{HclRule, TerraformUtils};
+use std::ops::Range;
+use std::sync::Arc;
 
-pub struct NoDeprecatedInterpolationRule;
+pub struct NoDeprecatedInterpolationRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl NoDeprecatedInterpolationRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
+
+/// Quoted strings that are nothing but one interpolated reference, with the bare reference
+struct Interpolations<'a> {
+    text: &'a str,
+    found: Vec<(Range<usize>, String)>,
+}
+
+impl Visit for Interpolations<'_> {
+    fn visit_expr(&mut self, expr: &Expression) {
+        // Heredocs are plain strings in modern Terraform too, so only quoted templates count.
+        // Only a reference can be unwrapped as is: other expressions are either computed
+        // values that need the template, or literals left to no-legacy-syntax.
+        if let Expression::StringTemplate(template) = expr
+            && let [Element::Interpolation(interpolation)] = template.iter().collect::<Vec<_>>().as_slice()
+            && matches!(interpolation.expr, Expression::Variable(_) | Expression::Traversal(_))
+            && let Some(span) = expr.span()
+            && let Some(reference) = interpolation.expr.span().and_then(|span| self.text.get(span))
+        {
+            self.found.push((span, reference.trim().to_string()));
+        }
+        visit::visit_expr(self, expr);
+    }
+}
 
 impl Rule for NoDeprecatedInterpolationRule {
     fn id(&self) -> &'static str {
@@ -18,43 +59,103 @@ impl Rule for NoDeprecatedInterpolationRule {
     }
 
     fn check(&self, ctx: &mut RuleContext) {
-        let line_index = LineIndex::new(ctx.text);
-        
-        // Look for deprecated interpolation syntax like "${var.name}" in strings
-        // that should just be var.name in modern Terraform
-        let deprecated_pattern = Regex::new(r#""[^"]*\$\{([^}]+)\}[^"]*""#).unwrap();
-
-        for (line_num, line) in ctx.text.lines().enumerate() {
-            for mat in deprecated_pattern.find_iter(line) {
-                // Skip if this is a complex interpolation that actually needs ${}
-                let interpolation_content = &line[mat.start()..mat.end()];
-                if interpolation_content.contains(" ") || 
-                   interpolation_content.contains("+") || 
-                   interpolation_content.contains("*") ||
-                   interpolation_content.contains("/") ||
-                   interpolation_content.contains("(") {
-                    continue; // This is a complex expression that needs interpolation
-                }
-
-                let line_start = ctx.text.lines().take(line_num).map(|l| l.len() + 1).sum::<usize>();
-                let start_pos = line_index.to_pos(line_start + mat.start());
-                let end_pos = line_index.to_pos(line_start + mat.end());
-
-                let diagnostic = Diagnostic {
-                    rule_id: self.id().to_string(),
-                    message: "Deprecated interpolation syntax found. Use direct variable reference instead".to_string(),
-                    severity: "warn".to_string(),
-                    range: Range {
-                        start: start_pos,
-                        end: end_pos,
-                    },
-                    code: Some("DEPRECATED_INTERPOLATION".to_string()),
-                    suggest: None,
-                    docs_url: Some("https://forseti.dev/rules/terraform/no-deprecated-interpolation".to_string()),
-                };
-
-                ctx.report(diagnostic);
-            }
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for NoDeprecatedInterpolationRule {}
+
+impl HclRule for NoDeprecatedInterpolationRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        // Interpolation is the only way to write expressions in JSON syntax
+        if TerraformUtils::is_json_syntax(ctx.uri) {
+            return;
         }
+
+        let mut interpolations = Interpolations {
+            text: ctx.text,
+            found: Vec::new(),
+        };
+        interpolations.visit_body(body);
+
+        for (span, reference) in interpolations.found {
+            let suggest = vec![SuggestFix {
+                title: format!("Replace with `{}`", reference),
+                fix: Some(Fix {
+                    range: TerraformUtils::span_to_range(&span, ctx.text),
+                    text: reference,
+                }),
+            }];
+
+            DiagnosticBuilder::new(
+                self.id(),
+                "Deprecated interpolation syntax found. Use direct variable reference instead",
+            )
+            .with_span(&span, ctx.text)
+            .with_code("DEPRECATED_INTERPOLATION")
+            .with_suggestions(Some(suggest))
+            .report(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{apply_fix, codes, run};
+    use serde_json::json;
+
+    fn check(text: &str) -> Vec<forseti_sdk::core::Diagnostic> {
+        let rule = NoDeprecatedInterpolationRule::new(Arc::new(DocumentCache::new()));
+        run(&rule, "main.tf", text, json!("error"))
+    }
+
+    #[test]
+    fn pure_references_are_unwrapped() {
+        let text = r#"locals {
+  a = "${var.name}"
+  b = "${aws_instance.web[0].id}"
+  c = "prefix-${var.name}"
+  d = "${var.a} ${var.b}"
+  e = "${upper(var.name)}"
+  f = "${var.enabled ? 1 : 0}"
+  g = "${2}"
+}
+"#;
+        let diagnostics = check(text);
+        assert_eq!(codes(&diagnostics), ["DEPRECATED_INTERPOLATION", "DEPRECATED_INTERPOLATION"]);
+        let fixed = apply_fix(text, &diagnostics[0]).unwrap();
+        assert!(fixed.contains("  a = var.name\n"));
+        let fixed = apply_fix(text, &diagnostics[1]).unwrap();
+        assert!(fixed.contains("  b = aws_instance.web[0].id\n"));
+    }
+
+    #[test]
+    fn fixes_keep_their_place_in_crlf_files() {
+        let text = "# first\r\n# second\r\nlocals {\r\n  a = \"${var.name}\"\r\n}\r\n";
+        let diagnostics = check(text);
+        assert_eq!(codes(&diagnostics), ["DEPRECATED_INTERPOLATION"]);
+        assert_eq!(
+            apply_fix(text, &diagnostics[0]).unwrap(),
+            "# first\r\n# second\r\nlocals {\r\n  a = var.name\r\n}\r\n"
+        );
+    }
+
+    #[test]
+    fn comments_and_heredocs_are_ignored() {
+        let text = r#"# a = "${var.name}"
+locals {
+  // b = "${var.name}"
+  c = <<EOT
+"${var.name}"
+EOT
+}
+"#;
+        assert!(check(text).is_empty());
     }
 }
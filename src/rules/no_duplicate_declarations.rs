@@ -0,0 +1,147 @@
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::structure::Body;
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::module::Declaration;
+use crate::options::TerraformRule;
+use crate::utils::{HclRule, TerraformUtils};
+use std::sync::Arc;
+
+pub struct NoDuplicateDeclarationsRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl NoDuplicateDeclarationsRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+
+    /// `main.tf:12` style location of a declaration, for use in messages
+    fn location(declaration: &Declaration) -> String {
+        let file = declaration
+            .uri
+            .rsplit('/')
+            .next()
+            .unwrap_or(&declaration.uri);
+        format!("{}:{}", file, declaration.range.start.line + 1)
+    }
+}
+
+impl Rule for NoDuplicateDeclarationsRule {
+    fn id(&self) -> &'static str {
+        "no-duplicate-declarations"
+    }
+
+    fn description(&self) -> &'static str {
+        "Detects variables, outputs, locals, resources, data sources, module calls and providers declared more than once in a module"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("error".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for NoDuplicateDeclarationsRule {}
+
+impl HclRule for NoDuplicateDeclarationsRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, _body: &Body, ctx: &mut RuleContext) {
+        // Override files redeclare blocks on purpose; Terraform merges them in
+        if TerraformUtils::is_override_file(ctx.uri) {
+            return;
+        }
+
+        // Duplicates may be split across any files of the module
+        let module = self.documents.module_for(ctx.uri, ctx.text);
+        let index = &module.index;
+        let categories: [(&str, &str, &[Declaration]); 7] = [
+            ("variable", "", &index.variables),
+            ("output", "", &index.outputs),
+            ("local value", "local.", &index.locals),
+            ("resource", "", &index.resources),
+            ("data source", "data.", &index.data_sources),
+            ("module call", "module.", &index.module_calls),
+            ("provider configuration", "", &index.providers),
+        ];
+
+        for (kind, prefix, declarations) in categories {
+            for (position, declaration) in declarations.iter().enumerate() {
+                if declaration.uri != ctx.uri {
+                    continue;
+                }
+                // Files are indexed in the order Terraform loads them
+                let Some(first) = declarations[..position]
+                    .iter()
+                    .find(|d| d.name == declaration.name && !TerraformUtils::is_override_file(&d.uri))
+                else {
+                    continue;
+                };
+
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Duplicate {} '{}{}'; first defined at {}",
+                        kind,
+                        prefix,
+                        declaration.name,
+                        Self::location(first)
+                    ),
+                )
+                .with_range(declaration.range)
+                .with_code("DUPLICATE_DECLARATION")
+                .report(ctx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+    use serde_json::json;
+
+    #[test]
+    fn override_files_are_not_duplicates() {
+        let directory = std::env::temp_dir().join(format!("forseti-duplicates-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let files = [
+            ("a_override.tf", "variable \"region\" {\n  default = \"eu-west-1\"\n}\n"),
+            ("main.tf", "variable \"region\" {}\nvariable \"zone\" {}\n"),
+            ("other.tf", "variable \"zone\" {}\n"),
+            ("override.tf", "variable \"region\" {}\n"),
+        ];
+        for (name, text) in files {
+            std::fs::write(directory.join(name), text).unwrap();
+        }
+
+        let rule = NoDuplicateDeclarationsRule::new(Arc::new(DocumentCache::new()));
+        let found: Vec<(&str, Vec<String>)> = files
+            .iter()
+            .map(|(name, text)| {
+                let uri = format!("file://{}", directory.join(name).display());
+                let diagnostics = run(&rule, &uri, text, json!("error"));
+                (*name, codes(&diagnostics).into_iter().map(str::to_string).collect())
+            })
+            .collect();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(
+            found,
+            [
+                ("a_override.tf", vec![]),
+                ("main.tf", vec![]),
+                ("other.tf", vec!["DUPLICATE_DECLARATION".to_string()]),
+                ("override.tf", vec![]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,455 @@
+use forseti_sdk::core::{ConfigType, Fix, SuggestFix};
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::expr::{Expression, FuncCall, Traversal, TraversalOperator};
+use hcl::edit::structure::{Attribute, Block, Body};
+use hcl::edit::template::Element;
+use hcl::edit::visit::{self, Visit};
+use regex::Regex;
+use serde_json::{Value, json};
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::options::{OptionSpec, TerraformRule};
+use crate::utils::{HclRule, TerraformUtils};
+use std::ops::Range;
+use std::sync::{Arc, LazyLock};
+
+/// Diagnostic codes, one per legacy form, also used to select the checks
+const CHECKS: [&str; 6] = [
+    "QUOTED_TYPE",
+    "QUOTED_REFERENCE",
+    "LEGACY_INDEX",
+    "LEGACY_SPLAT",
+    "LEGACY_FUNCTION",
+    "INTERPOLATED_LITERAL",
+];
+
+/// What a quoted reference must look like to be unquoted safely
+static REFERENCE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^[A-Za-z_][\w-]*(\.[A-Za-z_][\w-]*|\[\d+\])*$").expect("valid reference pattern")
+});
+
+/// A piece of 0.11-era syntax and its modern replacement, when there is a safe one
+struct Finding {
+    code: &'static str,
+    message: String,
+    span: Range<usize>,
+    replacement: Option<String>,
+}
+
+/// Collects legacy syntax from a configuration body
+struct LegacySyntax<'a> {
+    text: &'a str,
+    findings: Vec<Finding>,
+}
+
+impl LegacySyntax<'_> {
+    fn source(&self, span: Option<Range<usize>>) -> Option<&str> {
+        self.text.get(span?)
+    }
+
+    /// `type = "string"` in a variable block
+    fn quoted_type(&mut self, attr: &Attribute) {
+        let Expression::String(value) = &attr.value else {
+            return;
+        };
+        // 0.11 only had these three, and its collections were always of strings
+        let replacement = match value.as_str() {
+            "string" => Some("string".to_string()),
+            "list" | "map" => Some(format!("{}(string)", value.as_str())),
+            _ => None,
+        };
+        let message = match &replacement {
+            Some(replacement) => format!("Quoted type constraints are deprecated; use {}", replacement),
+            None => "Quoted type constraints are deprecated; use a type expression".to_string(),
+        };
+        self.findings.push(Finding {
+            code: "QUOTED_TYPE",
+            message,
+            span: attr.value.span().unwrap_or(0..0),
+            replacement,
+        });
+    }
+
+    /// A reference written as a string, such as `depends_on = ["aws_instance.web"]`
+    fn quoted_reference(&mut self, argument: &str, expr: &Expression) {
+        let Expression::String(value) = expr else {
+            return;
+        };
+        let reference = value.as_str();
+        self.findings.push(Finding {
+            code: "QUOTED_REFERENCE",
+            message: format!("Quoted references in {} are deprecated; use {}", argument, reference),
+            span: expr.span().unwrap_or(0..0),
+            replacement: REFERENCE.is_match(reference).then(|| reference.to_string()),
+        });
+    }
+
+    fn quoted_references(&mut self, attr: &Attribute) {
+        let argument = attr.key.as_str();
+        match &attr.value {
+            // `ignore_changes = ["*"]` became the `all` keyword
+            Expression::Array(items)
+                if argument == "ignore_changes"
+                    && items.len() == 1
+                    && items.iter().next().and_then(Expression::as_str) == Some("*") =>
+            {
+                self.findings.push(Finding {
+                    code: "QUOTED_REFERENCE",
+                    message: "ignore_changes = [\"*\"] is deprecated; use all".to_string(),
+                    span: attr.value.span().unwrap_or(0..0),
+                    replacement: Some("all".to_string()),
+                });
+            }
+            Expression::Array(items) => {
+                for item in items.iter() {
+                    self.quoted_reference(argument, item);
+                }
+            }
+            // `providers = { aws = "aws.west" }` in module blocks
+            Expression::Object(object) => {
+                for (_, value) in object.iter() {
+                    self.quoted_reference(argument, value.expr());
+                }
+            }
+            other => self.quoted_reference(argument, other),
+        }
+    }
+
+    /// `foo.0.bar` and `foo.*.id`
+    fn traversal(&mut self, traversal: &Traversal) {
+        let operators: Vec<_> = traversal.operators.iter().collect();
+        for (position, operator) in operators.iter().enumerate() {
+            let span = operator.span().unwrap_or(0..0);
+            // The operator span starts at its dot, after any whitespace
+            let dotted = self.source(Some(span.clone())).is_some_and(|s| s.starts_with('.'));
+            match operator.value() {
+                TraversalOperator::LegacyIndex(index) => {
+                    let replacement = format!("[{}]", index.value());
+                    self.findings.push(Finding {
+                        code: "LEGACY_INDEX",
+                        message: format!("Legacy index syntax .{} is deprecated; use {}", index.value(), replacement),
+                        span,
+                        replacement: dotted.then_some(replacement),
+                    });
+                }
+                TraversalOperator::AttrSplat(_) => {
+                    // `[*]` also applies later index operators to each element, so the
+                    // rewrite only keeps the meaning when attribute accesses follow
+                    let attributes_only = operators[position + 1..]
+                        .iter()
+                        .all(|op| matches!(op.value(), TraversalOperator::GetAttr(_)));
+                    self.findings.push(Finding {
+                        code: "LEGACY_SPLAT",
+                        message: "Legacy splat syntax .* is deprecated; use [*]".to_string(),
+                        span,
+                        replacement: (dotted && attributes_only).then(|| "[*]".to_string()),
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// `list(...)` and `map(...)`, removed in Terraform 0.15
+    fn function(&mut self, call: &FuncCall) {
+        let name = call.name.name.as_str();
+        if !call.name.namespace.is_empty() || !matches!(name, "list" | "map") {
+            return;
+        }
+        let args: Option<Vec<&str>> = call.args.iter().map(|arg| self.source(arg.span())).collect();
+        let args = args.filter(|_| !call.args.expand_final());
+
+        let (message, replacement) = if name == "list" {
+            (
+                "The list() function was removed in Terraform 0.15; use tolist([...])",
+                args.map(|args| format!("tolist([{}])", args.join(", "))),
+            )
+        } else {
+            let pairs = args.filter(|args| args.len() % 2 == 0).map(|args| {
+                args.chunks(2)
+                    .zip(call.args.iter().step_by(2))
+                    .map(|(pair, key)| {
+                        // Quoted keys are valid object keys; anything else needs parentheses
+                        if matches!(key, Expression::String(_)) {
+                            format!("{} = {}", pair[0], pair[1])
+                        } else {
+                            format!("({}) = {}", pair[0], pair[1])
+                        }
+                    })
+                    .collect::<Vec<_>>()
+            });
+            (
+                "The map() function was removed in Terraform 0.15; use tomap({...})",
+                pairs.map(|pairs| format!("tomap({{ {} }})", pairs.join(", "))),
+            )
+        };
+        self.findings.push(Finding {
+            code: "LEGACY_FUNCTION",
+            message: message.to_string(),
+            span: call.span().unwrap_or(0..0),
+            replacement,
+        });
+    }
+
+    /// `"${true}"` and `"${42}"`
+    fn interpolated_literal(&mut self, expr: &Expression) {
+        let Expression::StringTemplate(template) = expr else {
+            return;
+        };
+        let elements: Vec<&Element> = template.iter().collect();
+        let [Element::Interpolation(interpolation)] = elements.as_slice() else {
+            return;
+        };
+        let literal = match &interpolation.expr {
+            Expression::Bool(value) => value.value().to_string(),
+            Expression::Number(value) => value.value().to_string(),
+            _ => return,
+        };
+        let Some(span) = expr.span() else {
+            return;
+        };
+        self.findings.push(Finding {
+            code: "INTERPOLATED_LITERAL",
+            message: format!("Interpolating a literal is deprecated; use {}", literal),
+            span,
+            replacement: Some(literal),
+        });
+    }
+}
+
+impl Visit for LegacySyntax<'_> {
+    fn visit_block(&mut self, block: &Block) {
+        for attr in block.body.attributes() {
+            match (block.ident.as_str(), attr.key.as_str()) {
+                ("variable", "type") => self.quoted_type(attr),
+                (_, "depends_on") | ("lifecycle", "ignore_changes") => self.quoted_references(attr),
+                ("resource" | "data", "provider") | ("module", "providers") => self.quoted_references(attr),
+                _ => {}
+            }
+        }
+        if !block.has_ident("variable") {
+            visit::visit_block(self, block);
+            return;
+        }
+        // `list(string)` in a type constraint is a type, not the removed function
+        for structure in block.body.iter() {
+            match structure.as_attribute() {
+                Some(attr) if attr.key.as_str() == "type" => {}
+                _ => self.visit_structure(structure),
+            }
+        }
+    }
+
+    fn visit_expr(&mut self, expr: &Expression) {
+        match expr {
+            Expression::Traversal(traversal) => self.traversal(traversal),
+            Expression::FuncCall(call) => {
+                // Type constructors only hold types, such as `object({ tags = map(string) })`
+                if call.name.namespace.is_empty()
+                    && matches!(call.name.name.as_str(), "object" | "tuple" | "optional")
+                {
+                    return;
+                }
+                self.function(call);
+            }
+            Expression::StringTemplate(_) => self.interpolated_literal(expr),
+            _ => {}
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+pub struct NoLegacySyntaxRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl NoLegacySyntaxRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
+
+/// Each entry must name a known check
+fn validate_checks(value: &Value) -> Result<(), String> {
+    for item in value.as_array().into_iter().flatten() {
+        if !item.as_str().is_some_and(|code| CHECKS.contains(&code)) {
+            return Err(format!("unknown check {}, expected one of {}", item, CHECKS.join(", ")));
+        }
+    }
+    Ok(())
+}
+
+impl Rule for NoLegacySyntaxRule {
+    fn id(&self) -> &'static str {
+        "no-legacy-syntax"
+    }
+
+    fn description(&self) -> &'static str {
+        "Flags Terraform 0.11-era syntax (quoted types and references, legacy index and splat, list()/map(), interpolated literals) and offers the modern equivalent"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("warn".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for NoLegacySyntaxRule {
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new(
+                "checks",
+                "Legacy forms to report, by diagnostic code: QUOTED_TYPE, QUOTED_REFERENCE, LEGACY_INDEX, LEGACY_SPLAT, LEGACY_FUNCTION and INTERPOLATED_LITERAL",
+                ConfigType::Array,
+                json!(CHECKS),
+            )
+            .with_validator(validate_checks),
+        ]
+    }
+}
+
+impl HclRule for NoLegacySyntaxRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        // JSON syntax has no native types or references, and variable files hold values only
+        if TerraformUtils::is_json_syntax(ctx.uri) || TerraformUtils::is_tfvars(ctx.uri) {
+            return;
+        }
+        let checks = self.options(ctx).string_list("checks");
+
+        let mut legacy = LegacySyntax {
+            text: ctx.text,
+            findings: Vec::new(),
+        };
+        legacy.visit_body(body);
+
+        for finding in legacy.findings {
+            if !checks.iter().any(|code| code == finding.code) {
+                continue;
+            }
+            let suggestions = finding.replacement.map(|replacement| {
+                vec![SuggestFix {
+                    title: format!("Replace with `{}`", replacement),
+                    fix: Some(Fix {
+                        range: TerraformUtils::span_to_range(&finding.span, ctx.text),
+                        text: replacement,
+                    }),
+                }]
+            });
+            DiagnosticBuilder::new(self.id(), finding.message)
+                .with_span(&finding.span, ctx.text)
+                .with_code(finding.code)
+                .with_suggestions(suggestions)
+                .report(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{apply_fix, codes, run};
+
+    fn check(text: &str) -> Vec<forseti_sdk::core::Diagnostic> {
+        let rule = NoLegacySyntaxRule::new(Arc::new(DocumentCache::new()));
+        run(&rule, "main.tf", text, json!("warn"))
+    }
+
+    #[test]
+    fn type_constraints_are_not_legacy_functions() {
+        let text = r#"variable "names" {
+  type = list(string)
+}
+
+variable "settings" {
+  type = map(object({
+    tags  = map(string)
+    ports = optional(list(number))
+    pair  = tuple([list(string), string])
+  }))
+}
+"#;
+        assert!(check(text).is_empty());
+    }
+
+    #[test]
+    fn list_function_is_rewritten() {
+        let text = "locals {\n  names = list(\"a\")\n}\n";
+        let diagnostics = check(text);
+        assert_eq!(codes(&diagnostics), ["LEGACY_FUNCTION"]);
+        assert_eq!(
+            apply_fix(text, &diagnostics[0]).unwrap(),
+            "locals {\n  names = tolist([\"a\"])\n}\n"
+        );
+    }
+
+    #[test]
+    fn map_function_is_rewritten() {
+        let text = "locals {\n  tags = map(\"Name\", \"web\", var.key, 1)\n}\n";
+        let diagnostics = check(text);
+        assert_eq!(codes(&diagnostics), ["LEGACY_FUNCTION"]);
+        assert_eq!(
+            apply_fix(text, &diagnostics[0]).unwrap(),
+            "locals {\n  tags = tomap({ \"Name\" = \"web\", (var.key) = 1 })\n}\n"
+        );
+    }
+
+    #[test]
+    fn quoted_types_are_rewritten() {
+        let text = "variable \"a\" {\n  type = \"list\"\n}\nvariable \"b\" {\n  type = \"string\"\n}\n";
+        let diagnostics = check(text);
+        assert_eq!(codes(&diagnostics), ["QUOTED_TYPE", "QUOTED_TYPE"]);
+        assert!(apply_fix(text, &diagnostics[0]).unwrap().contains("type = list(string)"));
+        assert!(apply_fix(text, &diagnostics[1]).unwrap().contains("type = string"));
+    }
+
+    #[test]
+    fn unknown_quoted_types_have_no_fix() {
+        let diagnostics = check("variable \"a\" {\n  type = \"foo\"\n}\n");
+        assert_eq!(codes(&diagnostics), ["QUOTED_TYPE"]);
+        assert!(diagnostics[0].suggest.is_none());
+    }
+
+    #[test]
+    fn legacy_index_and_splat_are_rewritten() {
+        let text = "locals {\n  a = aws_instance.web.0.id\n  b = aws_instance.web.*.id\n  c = aws_instance.web.*.tags[0]\n}\n";
+        let diagnostics = check(text);
+        assert_eq!(codes(&diagnostics), ["LEGACY_INDEX", "LEGACY_SPLAT", "LEGACY_SPLAT"]);
+        assert!(apply_fix(text, &diagnostics[0]).unwrap().contains("a = aws_instance.web[0].id"));
+        assert!(apply_fix(text, &diagnostics[1]).unwrap().contains("b = aws_instance.web[*].id"));
+        // `[*]` would also apply the index to each element
+        assert!(diagnostics[2].suggest.is_none());
+    }
+
+    #[test]
+    fn quoted_references_and_literals_are_rewritten() {
+        let text = r#"resource "aws_instance" "web" {
+  count      = "${2}"
+  provider   = "aws.west"
+  depends_on = ["aws_security_group.web"]
+
+  lifecycle {
+    ignore_changes = ["*"]
+  }
+}
+"#;
+        let diagnostics = check(text);
+        assert_eq!(
+            codes(&diagnostics),
+            ["QUOTED_REFERENCE", "QUOTED_REFERENCE", "INTERPOLATED_LITERAL", "QUOTED_REFERENCE"]
+        );
+        let fixed: Vec<String> = diagnostics.iter().map(|d| apply_fix(text, d).unwrap()).collect();
+        assert!(fixed[0].contains("provider   = aws.west\n"));
+        assert!(fixed[1].contains("depends_on = [aws_security_group.web]"));
+        assert!(fixed[2].contains("count      = 2\n"));
+        assert!(fixed[3].contains("ignore_changes = all\n"));
+    }
+}
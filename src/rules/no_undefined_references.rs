@@ -0,0 +1,270 @@
+use forseti_sdk::core::{Fix, SuggestFix};
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::structure::Body;
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::module::{Declaration, ModuleIndex};
+use crate::options::TerraformRule;
+use crate::references::{self, Reference};
+use crate::utils::{HclRule, TerraformUtils};
+use std::sync::Arc;
+
+/// Reference roots Terraform provides itself, which never need a declaration
+const BUILTIN_ROOTS: &[&str] = &["count", "each", "self", "path", "terraform", "ephemeral"];
+
+/// What a reference points at, resolved from its root
+struct Target<'a> {
+    kind: &'static str,
+    /// Address prefix before the declaration name (`var.`, `data.`, ...)
+    prefix: &'static str,
+    name: String,
+    /// Index of the last attribute that is part of the name
+    last_attribute: usize,
+    declarations: &'a [Declaration],
+}
+
+pub struct NoUndefinedReferencesRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl NoUndefinedReferencesRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+
+    fn target<'a>(reference: &Reference, index: &'a ModuleIndex) -> Option<Target<'a>> {
+        let (kind, prefix, last_attribute, declarations) = match reference.root.as_str() {
+            "var" => ("variable", "var.", 0, &index.variables),
+            "local" => ("local value", "local.", 0, &index.locals),
+            "module" => ("module", "module.", 0, &index.module_calls),
+            "data" => ("data source", "data.", 1, &index.data_sources),
+            root if BUILTIN_ROOTS.contains(&root) => return None,
+            _ => ("resource", "", 0, &index.resources),
+        };
+
+        let mut parts: Vec<&str> = Vec::new();
+        if prefix.is_empty() {
+            parts.push(&reference.root);
+        }
+        for n in 0..=last_attribute {
+            parts.push(reference.attribute(n)?);
+        }
+
+        Some(Target {
+            kind,
+            prefix,
+            name: parts.join("."),
+            last_attribute,
+            declarations,
+        })
+    }
+}
+
+impl Rule for NoUndefinedReferencesRule {
+    fn id(&self) -> &'static str {
+        "no-undefined-references"
+    }
+
+    fn description(&self) -> &'static str {
+        "Reports references to variables, locals, modules, data sources and resources that are not declared in the module"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("error".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for NoUndefinedReferencesRule {}
+
+impl HclRule for NoUndefinedReferencesRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        // tfvars values cannot reference anything; valid-tfvars reports them as non-literal
+        if TerraformUtils::is_tfvars(ctx.uri) {
+            return;
+        }
+
+        // Declarations may live in any file of the module, not just this one
+        let module = self.documents.module_for(ctx.uri, ctx.text);
+
+        for reference in references::body_references(body) {
+            let Some(target) = Self::target(&reference, &module.index) else {
+                continue;
+            };
+            if target.declarations.iter().any(|d| d.name == target.name) {
+                continue;
+            }
+
+            let address = format!("{}{}", target.prefix, target.name);
+            let span = reference.span_through(target.last_attribute);
+            let suggestion = TerraformUtils::closest_match(
+                &target.name,
+                target.declarations.iter().map(|d| d.name.as_str()),
+            )
+            .map(|name| format!("{}{}", target.prefix, name));
+
+            let message = match &suggestion {
+                Some(suggestion) => format!(
+                    "Reference to undeclared {} '{}'. Did you mean '{}'?",
+                    target.kind, address, suggestion
+                ),
+                None => format!("Reference to undeclared {} '{}'", target.kind, address),
+            };
+            let fix = suggestion.map(|suggestion| {
+                vec![SuggestFix {
+                    title: format!("Replace with `{}`", suggestion),
+                    fix: Some(Fix {
+                        range: TerraformUtils::span_to_range(&span, ctx.text),
+                        text: suggestion,
+                    }),
+                }]
+            });
+
+            DiagnosticBuilder::new(self.id(), message)
+                .with_span(&span, ctx.text)
+                .with_code("UNDEFINED_REFERENCE")
+                .with_suggestions(fix)
+                .report(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{apply_fix, codes, run};
+    use forseti_sdk::core::Diagnostic;
+    use serde_json::json;
+
+    /// Check `main.tf` of a module whose other file is `variables.tf`
+    fn check(name: &str, main: &str, variables: &str) -> Vec<Diagnostic> {
+        let directory = std::env::temp_dir().join(format!("forseti-undefined-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("main.tf"), main).unwrap();
+        std::fs::write(directory.join("variables.tf"), variables).unwrap();
+        let uri = format!("file://{}", directory.join("main.tf").display());
+        let rule = NoUndefinedReferencesRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, &uri, main, json!("error"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        diagnostics
+    }
+
+    #[test]
+    fn declarations_are_found_across_the_module() {
+        let main = r#"locals {
+  name = var.name
+}
+
+module "network" {
+  source = "./network"
+}
+
+data "aws_ami" "ubuntu" {}
+
+resource "aws_instance" "web" {
+  ami       = data.aws_ami.ubuntu.id
+  subnet_id = module.network.subnet_id
+  tags      = { Name = local.name }
+}
+
+output "id" {
+  value = aws_instance.web.id
+}
+"#;
+        let diagnostics = check("declared", main, "variable \"name\" {}\n");
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+    }
+
+    #[test]
+    fn undeclared_addresses_are_reported_with_suggestions() {
+        let main = r#"resource "aws_instance" "web" {
+  ami       = data.aws_ami.ubuntu.id
+  subnet_id = module.netwrk.subnet_id
+  tags      = { Name = local.nam, Env = var.enviroment }
+}
+
+output "id" {
+  value = aws_instanc.web.id
+}
+"#;
+        let variables = r#"resource "aws_instance" "web" {}
+
+variable "environment" {}
+
+locals {
+  name = "web"
+}
+
+module "network" {
+  source = "./network"
+}
+"#;
+        let diagnostics = check("undeclared", main, variables);
+        assert_eq!(codes(&diagnostics), ["UNDEFINED_REFERENCE"; 5]);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Reference to undeclared data source 'data.aws_ami.ubuntu'",
+                "Reference to undeclared module 'module.netwrk'. Did you mean 'module.network'?",
+                "Reference to undeclared local value 'local.nam'. Did you mean 'local.name'?",
+                "Reference to undeclared variable 'var.enviroment'. Did you mean 'var.environment'?",
+                "Reference to undeclared resource 'aws_instanc.web'. Did you mean 'aws_instance.web'?",
+            ]
+        );
+        let fixed = apply_fix(main, &diagnostics[3]).unwrap();
+        assert!(fixed.contains("Env = var.environment }"));
+    }
+
+    #[test]
+    fn builtin_roots_and_bound_names_are_not_references() {
+        let main = r#"resource "aws_instance" "web" {
+  count    = 2
+  for_each = toset(["a"])
+  ami      = "${path.module}/${terraform.workspace}-${count.index}-${each.key}"
+  tags     = { for key, value in { a = 1 } : key => value if value > 0 }
+  names    = [for item in ["a"] : upper(item)]
+
+  dynamic "ebs_block_device" {
+    for_each = ["sdb"]
+    iterator = device
+    content {
+      device_name = device.value
+    }
+  }
+
+  dynamic "network_interface" {
+    for_each = [0]
+    content {
+      device_index = network_interface.value
+    }
+  }
+
+  provisioner "local-exec" {
+    command = "echo ${self.id}"
+  }
+}
+"#;
+        let diagnostics = check("builtin", main, "");
+        assert!(diagnostics.is_empty(), "{:?}", diagnostics);
+
+        // Names bound by a `for` expression do not leak out of it
+        let leaked = check("leak", "output \"x\" {\n  value = [[for item in [] : item.id], item.id]\n}\n", "");
+        assert_eq!(codes(&leaked), ["UNDEFINED_REFERENCE"]);
+        assert_eq!(leaked[0].message, "Reference to undeclared resource 'item.id'");
+    }
+
+    #[test]
+    fn tfvars_files_are_left_to_valid_tfvars() {
+        let rule = NoUndefinedReferencesRule::new(Arc::new(DocumentCache::new()));
+        assert!(run(&rule, "file:///prod.tfvars", "name = var.other\n", json!("error")).is_empty());
+    }
+}
@@ -0,0 +1,201 @@
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::structure::Body;
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::module::Module;
+use crate::options::TerraformRule;
+use crate::references::{self, Reference};
+use crate::utils::HclRule;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Which locals and data sources of a module are read, directly or through other
+/// declarations that are themselves read
+#[derive(Default)]
+struct Liveness {
+    /// Addresses referenced from outside any local or data source
+    roots: Vec<String>,
+    /// Addresses each local or data source refers to
+    edges: HashMap<String, Vec<String>>,
+}
+
+impl Liveness {
+    fn build(module: &Module) -> Self {
+        let mut liveness = Self::default();
+        for file in &module.files {
+            if let Some(body) = &file.document.body {
+                liveness.add_body(body);
+            }
+        }
+        liveness
+    }
+
+    fn add_body(&mut self, body: &Body) {
+        for block in body.blocks() {
+            match block.ident.as_str() {
+                "locals" => {
+                    for attr in block.body.attributes() {
+                        let address = format!("local.{}", attr.key.as_str());
+                        let targets = Self::addresses(references::expression_references(&attr.value));
+                        self.edges.entry(address).or_default().extend(targets);
+                    }
+                }
+                "data" if block.labels.len() >= 2 => {
+                    let address = format!("data.{}.{}", block.labels[0].as_str(), block.labels[1].as_str());
+                    let targets = Self::addresses(references::block_references(block));
+                    self.edges.entry(address).or_default().extend(targets);
+                }
+                // Scoped data sources of `check` blocks
+                "check" => self.add_body(&block.body),
+                _ => self.roots.extend(Self::addresses(references::block_references(block))),
+            }
+        }
+    }
+
+    /// Addresses of the locals and data sources among `references`
+    fn addresses(references: Vec<Reference>) -> impl Iterator<Item = String> {
+        references.into_iter().filter_map(|reference| match reference.root.as_str() {
+            "local" => Some(format!("local.{}", reference.attribute(0)?)),
+            "data" => Some(format!("data.{}.{}", reference.attribute(0)?, reference.attribute(1)?)),
+            _ => None,
+        })
+    }
+
+    /// Every address reachable from the roots
+    fn live(&self) -> HashSet<&str> {
+        let mut live = HashSet::new();
+        let mut pending: Vec<&str> = self.roots.iter().map(String::as_str).collect();
+        while let Some(address) = pending.pop() {
+            if live.insert(address) {
+                pending.extend(self.edges.get(address).into_iter().flatten().map(String::as_str));
+            }
+        }
+        live
+    }
+
+    /// Whether any declaration refers to `address`, live or not
+    fn is_referenced_by_declaration(&self, address: &str) -> bool {
+        self.edges
+            .iter()
+            .any(|(from, targets)| from != address && targets.iter().any(|t| t == address))
+    }
+}
+
+pub struct NoUnusedDeclarationsRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl NoUnusedDeclarationsRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
+
+impl Rule for NoUnusedDeclarationsRule {
+    fn id(&self) -> &'static str {
+        "no-unused-declarations"
+    }
+
+    fn description(&self) -> &'static str {
+        "Flags locals and data sources that nothing in their module reads, including locals that only reference each other"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("warn".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for NoUnusedDeclarationsRule {}
+
+impl HclRule for NoUnusedDeclarationsRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, _body: &Body, ctx: &mut RuleContext) {
+        // Readers may live in any file of the module, not just this one
+        let module = self.documents.module_for(ctx.uri, ctx.text);
+        let liveness = Liveness::build(&module);
+        let live = liveness.live();
+
+        let declarations = module
+            .index
+            .locals
+            .iter()
+            .map(|d| ("Local value", "UNUSED_LOCAL", format!("local.{}", d.name), d))
+            .chain(
+                module
+                    .index
+                    .data_sources
+                    .iter()
+                    .map(|d| ("Data source", "UNUSED_DATA_SOURCE", format!("data.{}", d.name), d)),
+            );
+
+        for (kind, code, address, declaration) in declarations {
+            if declaration.uri != ctx.uri || live.contains(address.as_str()) {
+                continue;
+            }
+            let message = if liveness.is_referenced_by_declaration(&address) {
+                format!("{} '{}' is only referenced by unused declarations", kind, address)
+            } else {
+                format!("{} '{}' is declared but never used", kind, address)
+            };
+            DiagnosticBuilder::new(self.id(), message)
+                .with_range(declaration.range)
+                .with_code(code)
+                .report(ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+    use serde_json::json;
+
+    #[test]
+    fn declarations_read_only_by_unused_ones_are_unused() {
+        let directory = std::env::temp_dir().join(format!("forseti-unused-decl-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let main = r#"locals {
+  prefix = "app"
+  name   = "${local.prefix}-web"
+  region = data.aws_region.current.name
+}
+
+data "aws_region" "current" {}
+
+data "aws_ami" "ubuntu" {}
+
+data "aws_caller_identity" "current" {}
+"#;
+        std::fs::write(directory.join("main.tf"), main).unwrap();
+        std::fs::write(
+            directory.join("outputs.tf"),
+            "output \"ami\" {\n  value = data.aws_ami.ubuntu.id\n}\n\noutput \"region\" {\n  value = local.region\n}\n",
+        )
+        .unwrap();
+        let uri = format!("file://{}", directory.join("main.tf").display());
+        let rule = NoUnusedDeclarationsRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, &uri, main, json!("warn"));
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        // outputs.tf reads local.region, and through it data.aws_region.current, and data.aws_ami.ubuntu
+        assert_eq!(codes(&diagnostics), ["UNUSED_LOCAL", "UNUSED_LOCAL", "UNUSED_DATA_SOURCE"]);
+        let messages: Vec<&str> = diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Local value 'local.prefix' is only referenced by unused declarations",
+                "Local value 'local.name' is declared but never used",
+                "Data source 'data.aws_caller_identity.current' is declared but never used",
+            ]
+        );
+    }
+}
@@ -0,0 +1,107 @@
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::structure::Body;
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::options::TerraformRule;
+use crate::utils::{HclRule, TerraformUtils};
+use std::sync::Arc;
+
+pub struct NoUnusedVariablesRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl NoUnusedVariablesRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
+
+impl Rule for NoUnusedVariablesRule {
+    fn id(&self) -> &'static str {
+        "no-unused-variables"
+    }
+
+    fn description(&self) -> &'static str {
+        "Flags variables that are never referenced as var.<name> anywhere in their module"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("warn".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for NoUnusedVariablesRule {}
+
+impl HclRule for NoUnusedVariablesRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        // References may live in any file of the module, not just this one
+        let module = self.documents.module_for(ctx.uri, ctx.text);
+        let is_referenced = |name: &str| {
+            module
+                .references()
+                .any(|r| r.root == "var" && r.attribute(0) == Some(name))
+        };
+
+        for block in body.blocks() {
+            if block.has_ident("variable")
+                && let Some(label) = TerraformUtils::get_block_name(block, "variable")
+                && !is_referenced(label.as_str())
+            {
+                let span = TerraformUtils::label_span(label, ctx.text);
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!("Variable '{}' is declared but never used", label.as_str()),
+                )
+                .with_span(&span, ctx.text)
+                .with_code("UNUSED_VARIABLE")
+                .report(ctx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+    use serde_json::json;
+
+    #[test]
+    fn variables_are_used_from_any_file_of_the_module() {
+        let directory = std::env::temp_dir().join(format!("forseti-unused-var-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let variables = "variable \"name\" {}\n\nvariable \"region\" {}\n";
+        let saved_main = "output \"name\" {\n  value = var.name\n}\n";
+        std::fs::write(directory.join("variables.tf"), variables).unwrap();
+        std::fs::write(directory.join("main.tf"), saved_main).unwrap();
+        let variables_uri = format!("file://{}", directory.join("variables.tf").display());
+        let main_uri = format!("file://{}", directory.join("main.tf").display());
+        let rule = NoUnusedVariablesRule::new(Arc::new(DocumentCache::new()));
+
+        let diagnostics = run(&rule, &variables_uri, variables, json!("warn"));
+        assert_eq!(codes(&diagnostics), ["UNUSED_VARIABLE"]);
+        assert_eq!(diagnostics[0].message, "Variable 'region' is declared but never used");
+
+        // An unsaved buffer replaces its file on disk when the module is read...
+        let buffer = "variable \"name\" {}\n\nvariable \"zone\" {}\n";
+        let diagnostics = run(&rule, &variables_uri, buffer, json!("warn"));
+        assert_eq!(codes(&diagnostics), ["UNUSED_VARIABLE"]);
+        assert_eq!(diagnostics[0].message, "Variable 'zone' is declared but never used");
+        let buffer = "variable \"zone\" {}\n\noutput \"zone\" {\n  value = var.zone\n}\n";
+        assert!(run(&rule, &main_uri, buffer, json!("warn")).is_empty());
+
+        // ...and stops applying once the saved text is checked again
+        let diagnostics = run(&rule, &variables_uri, variables, json!("warn"));
+        assert_eq!(diagnostics[0].message, "Variable 'region' is declared but never used");
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -1,8 +1,19 @@
 use forseti_sdk::ruleset::{Rule, RuleContext};
-use hcl::Body;
+use hcl::edit::structure::Body;
+use crate::cache::DocumentCache;
+use crate::options::TerraformRule;
 use crate::utils::{HclRule, TerraformUtils};
+use std::sync::Arc;
 
-pub struct OutputDescriptionRequiredRule;
+pub struct OutputDescriptionRequiredRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl OutputDescriptionRequiredRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
 
 impl Rule for OutputDescriptionRequiredRule {
     fn id(&self) -> &'static str {
@@ -23,21 +34,27 @@ impl Rule for OutputDescriptionRequiredRule {
     }
 }
 
+impl TerraformRule for OutputDescriptionRequiredRule {}
+
 impl HclRule for OutputDescriptionRequiredRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
     fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
         for block in body.blocks() {
-            if block.identifier() == "output" {
-                if let Some(output_name) = TerraformUtils::get_block_name(block, "output") {
-                    if !TerraformUtils::has_description_attribute(block) {
-                        let diagnostic = TerraformUtils::create_missing_description_diagnostic(
-                            self.id(),
-                            "output",
-                            &output_name,
-                            ctx.text,
-                        );
-                        ctx.report(diagnostic);
-                    }
-                }
+            if block.has_ident("output")
+                && let Some(label) = TerraformUtils::get_block_name(block, "output")
+                && !TerraformUtils::has_description_attribute(block)
+            {
+                TerraformUtils::create_missing_description_diagnostic(
+                    self.id(),
+                    "output",
+                    block,
+                    label,
+                    ctx.text,
+                )
+                .report(ctx);
             }
         }
     }
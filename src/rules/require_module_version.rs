@@ -0,0 +1,225 @@
+use forseti_sdk::core::ConfigType;
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::structure::{Block, Body};
+use regex::Regex;
+use serde_json::json;
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::module_source::{ModuleSource, SourceKind};
+use crate::options::{OptionSpec, TerraformRule};
+use crate::utils::HclRule;
+use std::sync::{Arc, LazyLock};
+
+/// Full or abbreviated commit hashes
+static COMMIT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"^[0-9a-fA-F]{7,40}$").expect("valid commit pattern"));
+/// Release tags such as `v1.2.3`, `1.2` or `refs/tags/v2.0.0-rc1`
+static TAG: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(refs/tags/)?v?\d+(\.\d+)*([-+][0-9A-Za-z.-]+)?$").expect("valid tag pattern")
+});
+
+pub struct RequireModuleVersionRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl RequireModuleVersionRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+
+    /// Whether a ref names a commit or a release tag rather than a (moving) branch
+    fn is_immutable_ref(reference: &str) -> bool {
+        COMMIT.is_match(reference) || TAG.is_match(reference)
+    }
+
+    fn check_module(&self, block: &Block, allow_branches: bool, ctx: &mut RuleContext) {
+        let Some(name) = block.labels.first().map(|label| label.as_str()) else {
+            return;
+        };
+        let Some(source_attr) = block.body.get_attribute("source") else {
+            return;
+        };
+        // Terraform requires a literal source, anything else is reported by Terraform itself
+        let Some(source_text) = source_attr.value.as_str() else {
+            return;
+        };
+        let source = ModuleSource::parse(source_text);
+        let span = source_attr.value.span().unwrap_or(0..0);
+        let version_attr = block.body.get_attribute("version");
+
+        if source.kind == SourceKind::Registry {
+            if version_attr.is_none() {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Module '{}' uses registry source '{}' without a version constraint",
+                        name, source_text
+                    ),
+                )
+                .with_span(&span, ctx.text)
+                .with_code("MISSING_MODULE_VERSION")
+                .report(ctx);
+            }
+            return;
+        }
+
+        if let Some(version_attr) = version_attr {
+            DiagnosticBuilder::new(
+                self.id(),
+                format!(
+                    "Module '{}' sets 'version', which only applies to registry sources; the {} source ignores it",
+                    name, source.kind
+                ),
+            )
+            .with_span(&version_attr.key.span().unwrap_or(0..0), ctx.text)
+            .with_code("UNSUPPORTED_MODULE_VERSION")
+            .report(ctx);
+        }
+
+        if !source.kind.is_vcs() {
+            return;
+        }
+        match source.vcs_ref() {
+            None => {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Module '{}' uses {} source '{}' without '?ref=', so it tracks the default branch",
+                        name, source.kind, source_text
+                    ),
+                )
+                .with_span(&span, ctx.text)
+                .with_code("MISSING_MODULE_REF")
+                .report(ctx);
+            }
+            Some(reference) if !allow_branches && !Self::is_immutable_ref(reference) => {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Module '{}' pins ref '{}', which looks like a branch; use a tag or commit SHA",
+                        name, reference
+                    ),
+                )
+                .with_span(&span, ctx.text)
+                .with_code("MUTABLE_MODULE_REF")
+                .report(ctx);
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+impl Rule for RequireModuleVersionRule {
+    fn id(&self) -> &'static str {
+        "require-module-version"
+    }
+
+    fn description(&self) -> &'static str {
+        "Requires module calls to pin what they install: a version for registry sources and a ref for git and mercurial sources"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("warn".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for RequireModuleVersionRule {
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "allow_branches",
+            "Accept VCS refs that are neither a commit SHA nor a version tag (e.g. ?ref=main)",
+            ConfigType::Boolean,
+            json!(false),
+        )]
+    }
+}
+
+impl HclRule for RequireModuleVersionRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        let allow_branches = self.options(ctx).bool("allow_branches");
+        for block in body.blocks().filter(|block| block.has_ident("module")) {
+            self.check_module(block, allow_branches, ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+    use forseti_sdk::core::Diagnostic;
+
+    fn check(text: &str, options: serde_json::Value) -> Vec<Diagnostic> {
+        let rule = RequireModuleVersionRule::new(Arc::new(DocumentCache::new()));
+        run(&rule, "file:///main.tf", text, options)
+    }
+
+    #[test]
+    fn registry_sources_need_a_version() {
+        let text = r#"module "vpc" {
+  source = "terraform-aws-modules/vpc/aws"
+}
+
+module "eks" {
+  source  = "terraform-aws-modules/eks/aws"
+  version = "~> 20.0"
+}
+"#;
+        let diagnostics = check(text, json!("warn"));
+        assert_eq!(codes(&diagnostics), ["MISSING_MODULE_VERSION"]);
+        assert_eq!(diagnostics[0].range.start.line, 1);
+    }
+
+    #[test]
+    fn vcs_sources_need_a_ref() {
+        let text = r#"module "network" {
+  source = "git::https://example.com/network.git"
+}
+
+module "dns" {
+  source  = "git::https://example.com/dns.git?ref=v1.4.0"
+  version = "1.4.0"
+}
+"#;
+        let diagnostics = check(text, json!("warn"));
+        assert_eq!(codes(&diagnostics), ["MISSING_MODULE_REF", "UNSUPPORTED_MODULE_VERSION"]);
+        assert!(diagnostics[0].message.contains("tracks the default branch"));
+    }
+
+    #[test]
+    fn branch_refs_are_mutable_unless_allowed() {
+        let text = r#"module "tag" {
+  source = "git::https://example.com/a.git?ref=refs/tags/v2.0.0-rc1"
+}
+
+module "commit" {
+  source = "github.com/example/b?ref=51d462976d84fdea54b47d80dcabbf680badcdb8"
+}
+
+module "branch" {
+  source = "git::https://example.com/c.git?ref=main"
+}
+"#;
+        let diagnostics = check(text, json!("warn"));
+        assert_eq!(codes(&diagnostics), ["MUTABLE_MODULE_REF"]);
+        assert!(diagnostics[0].message.starts_with("Module 'branch' pins ref 'main'"));
+
+        assert!(check(text, json!(["warn", { "allow_branches": true }])).is_empty());
+    }
+
+    #[test]
+    fn local_sources_are_exempt() {
+        let text = "module \"app\" {\n  source = \"./modules/app\"\n}\n\nmodule \"shared\" {\n  source = \"../shared\"\n}\n";
+        assert!(check(text, json!("warn")).is_empty());
+    }
+}
@@ -1,8 +1,30 @@
-use forseti_sdk::ruleset::{Rule, RuleContext};
-use hcl::{Body, Block};
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::options::{OptionSpec, TerraformRule};
 use crate::utils::{HclRule, TerraformUtils};
+use crate::version::{Operator, VersionConstraints};
+use forseti_sdk::core::ConfigType;
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::expr::Expression;
+use hcl::edit::structure::{Block, Body};
+use serde_json::json;
+use std::sync::Arc;
+
+/// Values of the `policy` option
+const POLICY_NONE: &str = "none";
+const POLICY_PESSIMISTIC: &str = "pessimistic";
+const POLICY_UPPER_BOUND: &str = "upper_bound";
+
+pub struct RequireProviderVersionRule {
+    documents: Arc<DocumentCache>,
+}
 
-pub struct RequireProviderVersionRule;
+impl RequireProviderVersionRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
 
 impl Rule for RequireProviderVersionRule {
     fn id(&self) -> &'static str {
@@ -10,7 +32,7 @@ impl Rule for RequireProviderVersionRule {
     }
 
     fn description(&self) -> &'static str {
-        "Ensures Terraform providers specify version constraints for better dependency management and reproducibility"
+        "Ensures Terraform providers specify valid, satisfiable version constraints for better dependency management and reproducibility"
     }
 
     fn default_config(&self) -> serde_json::Value {
@@ -23,49 +45,189 @@ impl Rule for RequireProviderVersionRule {
     }
 }
 
+impl TerraformRule for RequireProviderVersionRule {
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new(
+                "policy",
+                "Extra requirement for every constraint: \"pessimistic\" (must use ~>) or \"upper_bound\" (must cap the version)",
+                ConfigType::Enum,
+                json!(POLICY_NONE),
+            )
+            .with_allowed_values(vec![json!(POLICY_NONE), json!(POLICY_PESSIMISTIC), json!(POLICY_UPPER_BOUND)]),
+        ]
+    }
+}
+
 impl HclRule for RequireProviderVersionRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
     fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        let options = self.options(ctx);
+        let policy = options.str("policy");
+
         // Look for terraform blocks and check required_providers
         for block in body.blocks() {
-            if block.identifier() == "terraform" {
-                self.check_required_providers_block(block, ctx);
+            if block.has_ident("terraform") {
+                self.check_required_providers_block(block, policy, ctx);
             }
         }
     }
 }
 
 impl RequireProviderVersionRule {
-    fn check_required_providers_block(&self, terraform_block: &Block, ctx: &mut RuleContext) {
+    fn check_required_providers_block(
+        &self,
+        terraform_block: &Block,
+        policy: &str,
+        ctx: &mut RuleContext,
+    ) {
         // Look for required_providers block within terraform block
-        for nested_block in terraform_block.body().blocks() {
-            if nested_block.identifier() == "required_providers" {
-                self.check_provider_entries(nested_block, ctx);
+        for nested_block in terraform_block.body.blocks() {
+            if nested_block.has_ident("required_providers") {
+                self.check_provider_entries(nested_block, policy, ctx);
             }
         }
     }
 
-    fn check_provider_entries(&self, required_providers_block: &Block, ctx: &mut RuleContext) {
+    fn check_provider_entries(
+        &self,
+        required_providers_block: &Block,
+        policy: &str,
+        ctx: &mut RuleContext,
+    ) {
         // Check each attribute in the required_providers block
-        for attr in required_providers_block.body().attributes() {
-            let provider_name = attr.key();
-            
-            // Check if this provider config has a version attribute
-            let has_version = match attr.expr() {
-                hcl::Expression::Object(obj) => {
-                    obj.iter().any(|(key, _)| {
-                        TerraformUtils::debug_to_string(key) == "version"
+        for attr in required_providers_block.body.attributes() {
+            let provider_name = attr.key.as_str();
+
+            // Find the version attribute of this provider config
+            let version = match &attr.value {
+                Expression::Object(obj) => obj
+                    .iter()
+                    .find(|(key, _)| {
+                        TerraformUtils::object_key_to_string(key).as_deref() == Some("version")
                     })
-                }
-                _ => false, // Provider is not an object, so no version specified
+                    .map(|(_, value)| value.expr()),
+                // Pre-0.13 shorthand: `aws = "~> 3.0"` is the version constraint itself
+                Expression::String(_) => Some(&attr.value),
+                _ => None, // Provider is not an object, so no version specified
             };
 
-            if !has_version {
-                let diagnostic = TerraformUtils::create_provider_version_diagnostic(
+            match version {
+                Some(version) => self.check_constraint(provider_name, version, policy, ctx),
+                None => TerraformUtils::create_provider_version_diagnostic(
                     provider_name,
+                    &attr.key.span().unwrap_or(0..0),
                     ctx.text,
+                )
+                .report(ctx),
+            }
+        }
+    }
+
+    /// Validate a provider's version constraint string and apply the configured policy
+    fn check_constraint(
+        &self,
+        provider_name: &str,
+        version: &Expression,
+        policy: &str,
+        ctx: &mut RuleContext,
+    ) {
+        let span = version.span().unwrap_or(0..0);
+        let report = |ctx: &mut RuleContext, code: &str, message: String| {
+            DiagnosticBuilder::new(self.id(), message)
+                .with_span(&span, ctx.text)
+                .with_code(code)
+                .report(ctx);
+        };
+
+        let Some(text) = version.as_str() else {
+            report(
+                ctx,
+                "INVALID_VERSION_CONSTRAINT",
+                format!(
+                    "Provider '{}' version must be a literal constraint string",
+                    provider_name
+                ),
+            );
+            return;
+        };
+        let constraints = match VersionConstraints::parse(text) {
+            Ok(constraints) => constraints,
+            Err(error) => {
+                report(
+                    ctx,
+                    "INVALID_VERSION_CONSTRAINT",
+                    format!(
+                        "Provider '{}' has an invalid version constraint \"{}\": {}",
+                        provider_name, text, error
+                    ),
                 );
-                ctx.report(diagnostic);
+                return;
+            }
+        };
+
+        if !constraints.is_satisfiable() {
+            report(
+                ctx,
+                "UNSATISFIABLE_VERSION_CONSTRAINT",
+                format!(
+                    "Provider '{}' version constraint \"{}\" cannot be satisfied by any version",
+                    provider_name, text
+                ),
+            );
+            return;
+        }
+
+        let violation = match policy {
+            POLICY_PESSIMISTIC if !constraints.uses_operator(Operator::Pessimistic) => {
+                Some("must use the '~>' operator")
             }
+            POLICY_UPPER_BOUND if !constraints.has_upper_bound() => {
+                Some("must have an upper bound")
+            }
+            _ => None,
+        };
+        if let Some(violation) = violation {
+            report(
+                ctx,
+                "VERSION_CONSTRAINT_POLICY",
+                format!(
+                    "Provider '{}' version constraint \"{}\" {}",
+                    provider_name, text, violation
+                ),
+            );
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+
+    fn check(text: &str, config: serde_json::Value) -> Vec<forseti_sdk::core::Diagnostic> {
+        let rule = RequireProviderVersionRule::new(Arc::new(DocumentCache::new()));
+        run(&rule, "main.tf", text, config)
+    }
+
+    #[test]
+    fn shorthand_string_is_the_version() {
+        let text = r#"terraform {
+  required_providers {
+    aws    = "~> 3.0"
+    google = ">= 4.0, < 3.0"
+    random = { source = "hashicorp/random" }
+  }
+}
+"#;
+        let diagnostics = check(text, json!(["error", { "policy": "pessimistic" }]));
+        assert_eq!(
+            codes(&diagnostics),
+            ["UNSATISFIABLE_VERSION_CONSTRAINT", "PROVIDER_VERSION"]
+        );
+        assert!(diagnostics[0].message.contains("'google'"));
+    }
+}
@@ -0,0 +1,270 @@
+use forseti_sdk::core::ConfigType;
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::structure::{Attribute, Body};
+use serde_json::{Value, json};
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::module::Module;
+use crate::options::{OptionSpec, TerraformRule};
+use crate::utils::HclRule;
+use crate::version::{Version, VersionConstraints};
+use std::sync::Arc;
+
+pub struct RequireTerraformVersionRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl RequireTerraformVersionRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+
+    /// `required_version` arguments of the `terraform` blocks in `body`
+    fn required_versions(body: &Body) -> impl Iterator<Item = &Attribute> {
+        body.blocks()
+            .filter(|block| block.has_ident("terraform"))
+            .filter_map(|block| block.body.get_attribute("required_version"))
+    }
+
+    fn check_constraint(&self, attr: &Attribute, minimum: Option<&Version>, ctx: &mut RuleContext) {
+        let span = attr.value.span().unwrap_or(0..0);
+        let Some(text) = attr.value.as_str() else {
+            DiagnosticBuilder::new(self.id(), "required_version must be a literal version constraint string")
+                .with_span(&span, ctx.text)
+                .with_code("INVALID_VERSION_CONSTRAINT")
+                .report(ctx);
+            return;
+        };
+
+        let constraints = match VersionConstraints::parse(text) {
+            Ok(constraints) => constraints,
+            Err(error) => {
+                DiagnosticBuilder::new(self.id(), format!("Invalid required_version \"{}\": {}", text, error))
+                    .with_span(&span, ctx.text)
+                    .with_code("INVALID_VERSION_CONSTRAINT")
+                    .report(ctx);
+                return;
+            }
+        };
+
+        if constraints.lower_bound().is_none() {
+            DiagnosticBuilder::new(
+                self.id(),
+                format!(
+                    "required_version \"{}\" has no lower bound, so any old Terraform release is accepted",
+                    text
+                ),
+            )
+            .with_span(&span, ctx.text)
+            .with_code("NO_LOWER_BOUND")
+            .report(ctx);
+        } else if let Some(minimum) = minimum
+            && constraints.allows_below(minimum)
+        {
+            DiagnosticBuilder::new(
+                self.id(),
+                format!(
+                    "required_version \"{}\" allows Terraform versions older than the minimum {}",
+                    text, minimum
+                ),
+            )
+            .with_span(&span, ctx.text)
+            .with_code("BELOW_MINIMUM_VERSION")
+            .report(ctx);
+        }
+    }
+
+    /// Report a root module without `required_version`, once: on the first file
+    /// with a `terraform` block, or the module's first file when there is none
+    fn check_missing(&self, module: &Module, ctx: &mut RuleContext) {
+        let bodies = || {
+            module
+                .files
+                .iter()
+                .filter_map(|file| Some((file, file.document.body.as_ref()?)))
+        };
+        if bodies().any(|(_, body)| Self::required_versions(body).next().is_some()) {
+            return;
+        }
+
+        let terraform_block = |body: &Body| body.blocks().any(|block| block.has_ident("terraform"));
+        let anchor = bodies()
+            .find(|(_, body)| terraform_block(body))
+            .or_else(|| bodies().next());
+        let Some((file, body)) = anchor else {
+            return;
+        };
+        if file.uri != ctx.uri {
+            return;
+        }
+
+        let span = body
+            .blocks()
+            .find(|block| block.has_ident("terraform"))
+            .and_then(|block| block.ident.span())
+            .unwrap_or(0..0);
+        DiagnosticBuilder::new(
+            self.id(),
+            "Root module does not declare terraform.required_version",
+        )
+        .with_span(&span, ctx.text)
+        .with_code("MISSING_REQUIRED_VERSION")
+        .report(ctx);
+    }
+}
+
+/// The minimum must itself be a plain version, or empty to disable the check
+fn validate_minimum_version(value: &Value) -> Result<(), String> {
+    match value.as_str() {
+        Some("") => Ok(()),
+        Some(version) if Version::parse(version).is_some() => Ok(()),
+        _ => Err(format!("{} is not a version like \"1.5.0\"", value)),
+    }
+}
+
+impl Rule for RequireTerraformVersionRule {
+    fn id(&self) -> &'static str {
+        "require-terraform-version"
+    }
+
+    fn description(&self) -> &'static str {
+        "Requires root modules to declare terraform.required_version and checks that the constraint is valid and has a sufficient lower bound"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("warn".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for RequireTerraformVersionRule {
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new(
+                "minimum_version",
+                "Oldest Terraform version the constraint may allow (e.g. \"1.5.0\"); empty to disable",
+                ConfigType::String,
+                json!(""),
+            )
+            .with_validator(validate_minimum_version),
+            OptionSpec::new(
+                "root_modules_only",
+                "Only require required_version in root modules (initialized, or configuring a backend)",
+                ConfigType::Boolean,
+                json!(true),
+            ),
+        ]
+    }
+}
+
+impl HclRule for RequireTerraformVersionRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        if !Module::is_configuration_file(std::path::Path::new(ctx.uri)) {
+            return;
+        }
+        let options = self.options(ctx);
+        let minimum = Version::parse(options.str("minimum_version"));
+        let root_modules_only = options.bool("root_modules_only");
+
+        for attr in Self::required_versions(body) {
+            self.check_constraint(attr, minimum.as_ref(), ctx);
+        }
+
+        let module = self.documents.module_for(ctx.uri, ctx.text);
+        if !root_modules_only || module.is_root() {
+            self.check_missing(&module, ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+    use forseti_sdk::core::Diagnostic;
+
+    /// Check each of `files` in a module directory holding all of them
+    fn check(name: &str, files: &[(&str, &str)], options: Value) -> Vec<Vec<Diagnostic>> {
+        let directory = std::env::temp_dir().join(format!("forseti-tf-version-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        for (file, text) in files {
+            std::fs::write(directory.join(file), text).unwrap();
+        }
+        let rule = RequireTerraformVersionRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = files
+            .iter()
+            .map(|(file, text)| {
+                let uri = format!("file://{}", directory.join(file).display());
+                run(&rule, &uri, text, options.clone())
+            })
+            .collect();
+        std::fs::remove_dir_all(&directory).unwrap();
+        diagnostics
+    }
+
+    #[test]
+    fn root_modules_must_declare_a_version_once() {
+        let files = [
+            ("main.tf", "resource \"null_resource\" \"a\" {}\n"),
+            ("backend.tf", "terraform {\n  backend \"s3\" {}\n}\n"),
+        ];
+        let diagnostics = check("root", &files, json!("warn"));
+        // Reported on the file with the terraform block, where the argument belongs
+        assert!(diagnostics[0].is_empty());
+        assert_eq!(codes(&diagnostics[1]), ["MISSING_REQUIRED_VERSION"]);
+        assert_eq!(diagnostics[1][0].range.start.line, 0);
+    }
+
+    #[test]
+    fn child_modules_are_exempt() {
+        let files = [("main.tf", "variable \"name\" {}\n")];
+        assert!(check("child", &files, json!("warn"))[0].is_empty());
+
+        let diagnostics = check("child-all", &files, json!(["warn", { "root_modules_only": false }]));
+        assert_eq!(codes(&diagnostics[0]), ["MISSING_REQUIRED_VERSION"]);
+    }
+
+    #[test]
+    fn constraints_are_validated() {
+        let text = r#"terraform {
+  required_version = ">= banana"
+}
+
+terraform {
+  required_version = "< 2.0"
+}
+
+terraform {
+  required_version = ">= 1.3, < 2.0"
+}
+
+terraform {
+  required_version = var.terraform_version
+}
+"#;
+        let diagnostics = check("constraints", &[("versions.tf", text)], json!(["warn", { "minimum_version": "1.5.0" }]));
+        assert_eq!(
+            codes(&diagnostics[0]),
+            [
+                "INVALID_VERSION_CONSTRAINT",
+                "NO_LOWER_BOUND",
+                "BELOW_MINIMUM_VERSION",
+                "INVALID_VERSION_CONSTRAINT",
+            ]
+        );
+        assert_eq!(
+            diagnostics[0][0].message,
+            "Invalid required_version \">= banana\": '>= banana' is not a valid version constraint"
+        );
+        assert!(diagnostics[0][2].message.ends_with("older than the minimum 1.5.0"));
+    }
+}
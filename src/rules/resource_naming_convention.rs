@@ -1,9 +1,26 @@
+use forseti_sdk::core::ConfigType;
 use forseti_sdk::ruleset::{Rule, RuleContext};
-use hcl::Body;
+use hcl::edit::Span;
+use hcl::edit::structure::Body;
 use regex::Regex;
+use serde_json::json;
+use crate::cache::DocumentCache;
+use crate::options::{OptionSpec, TerraformRule, validate_regex, validate_string_list};
 use crate::utils::{HclRule, TerraformUtils};
+use std::sync::Arc;
 
-pub struct ResourceNamingConventionRule;
+/// Default naming pattern: snake_case starting with a letter
+const SNAKE_CASE_PATTERN: &str = r"^[a-z][a-z0-9_]*$";
+
+pub struct ResourceNamingConventionRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl ResourceNamingConventionRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
 
 impl Rule for ResourceNamingConventionRule {
     fn id(&self) -> &'static str {
@@ -24,27 +41,109 @@ impl Rule for ResourceNamingConventionRule {
     }
 }
 
+impl TerraformRule for ResourceNamingConventionRule {
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new(
+                "pattern",
+                "Regular expression that block names must match",
+                ConfigType::String,
+                json!(SNAKE_CASE_PATTERN),
+            )
+            .with_validator(validate_regex),
+            OptionSpec::new(
+                "block_types",
+                "Block types whose names are checked (resource, data, variable, output, locals, module)",
+                ConfigType::Array,
+                json!(["resource", "data", "variable", "output", "locals"]),
+            )
+            .with_validator(validate_string_list),
+        ]
+    }
+}
+
 impl HclRule for ResourceNamingConventionRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
     fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
-        // Valid naming pattern: snake_case starting with letter
-        let valid_name_pattern = Regex::new(r"^[a-z][a-z0-9_]*$").unwrap();
+        let options = self.options(ctx);
+        let pattern = options.str("pattern");
+        let Ok(valid_name_pattern) = Regex::new(pattern) else {
+            return;
+        };
+        let block_types = options.string_list("block_types");
+        let convention = if pattern == SNAKE_CASE_PATTERN {
+            "snake_case convention".to_string()
+        } else {
+            format!("naming pattern '{}'", pattern)
+        };
 
         for block in body.blocks() {
-            let block_type = block.identifier();
-            
-            // Check naming for these block types
-            if matches!(block_type, "resource" | "data" | "variable" | "output" | "locals") {
-                if let Some(name) = TerraformUtils::get_block_name(block, block_type) {
-                    if !valid_name_pattern.is_match(&name) {
-                        let diagnostic = TerraformUtils::create_naming_convention_diagnostic(
-                            block_type,
-                            &name,
+            let block_type = block.ident.as_str();
+            if !block_types.iter().any(|t| t == block_type) {
+                continue;
+            }
+
+            if block_type == "locals" {
+                // Locals have no label; each attribute key is a name of its own
+                for attr in block.body.attributes() {
+                    if !valid_name_pattern.is_match(attr.key.as_str()) {
+                        TerraformUtils::create_naming_convention_diagnostic(
+                            "local",
+                            attr.key.as_str(),
+                            &convention,
+                            &attr.key.span().unwrap_or(0..0),
                             ctx.text,
-                        );
-                        ctx.report(diagnostic);
+                        )
+                        .report(ctx);
                     }
                 }
+            } else if let Some(label) = TerraformUtils::get_block_name(block, block_type)
+                && !valid_name_pattern.is_match(label.as_str())
+            {
+                TerraformUtils::create_naming_convention_diagnostic(
+                    block_type,
+                    label.as_str(),
+                    &convention,
+                    &TerraformUtils::label_span(label, ctx.text),
+                    ctx.text,
+                )
+                .report(ctx);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{covered, run};
+    use serde_json::json;
+
+    #[test]
+    fn names_are_located_by_their_own_span() {
+        // Each bad name also appears earlier in the file, where a text search would land
+        let text = r#"# WebServer replaces the old Bad_Local setup
+variable "note" {
+  default = "WebServer"
+}
+
+locals {
+  name      = "Bad_Local"
+  Bad_Local = 1
+}
+
+resource "aws_instance" "WebServer" {}
+"#;
+        let rule = ResourceNamingConventionRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, "file:///main.tf", text, json!("error"));
+        assert_eq!(diagnostics.len(), 2);
+        let located: Vec<(u32, &str)> = diagnostics
+            .iter()
+            .map(|d| (d.range.start.line, covered(text, d)))
+            .collect();
+        assert_eq!(located, [(7, "Bad_Local"), (10, "WebServer")]);
+    }
+}
@@ -0,0 +1,484 @@
+use forseti_sdk::core::{ConfigType, Fix, SuggestFix};
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::expr::Expression;
+use hcl::edit::structure::{Block, Body};
+use serde_json::{Value, json};
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::lock_file::{self, LOCK_FILE_NAME, LockFile};
+use crate::module::Module;
+use crate::module_source::{ModuleSource, SourceKind};
+use crate::options::{OptionSpec, TerraformRule};
+use crate::utils::TerraformUtils;
+use crate::version::{Version, VersionConstraints};
+use std::collections::BTreeSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// A provider declared in `required_providers`
+struct ProviderRequirement {
+    local_name: String,
+    address: String,
+    key_span: Range<usize>,
+    /// The `version` constraint and its span, when given as a string
+    version: Option<(String, Range<usize>)>,
+}
+
+/// Providers a module tree needs, by normalized source address
+struct ModuleProviders {
+    addresses: BTreeSet<String>,
+    /// False when a module call cannot be followed (registry, git, ...), so
+    /// requirements of that module are unknown
+    complete: bool,
+}
+
+pub struct ValidLockFileRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl ValidLockFileRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+
+    /// Every entry of `required_providers` blocks in `body`
+    fn requirements(body: &Body) -> Vec<ProviderRequirement> {
+        body.blocks()
+            .filter(|block| block.has_ident("terraform"))
+            .flat_map(|block| block.body.blocks())
+            .filter(|block| block.has_ident("required_providers"))
+            .flat_map(|block| block.body.attributes())
+            .map(|attr| {
+                let local_name = attr.key.as_str().to_string();
+                let (source, version) = match &attr.value {
+                    Expression::Object(object) => {
+                        let property = |name: &str| {
+                            object
+                                .iter()
+                                .find(|(key, _)| TerraformUtils::object_key_to_string(key).as_deref() == Some(name))
+                                .map(|(_, value)| value.expr())
+                        };
+                        (property("source").and_then(Expression::as_str), property("version"))
+                    }
+                    // Terraform 0.12 shorthand: `aws = "~> 3.0"`
+                    value @ Expression::String(_) => (None, Some(value)),
+                    _ => (None, None),
+                };
+                ProviderRequirement {
+                    address: Self::address(&local_name, source),
+                    version: version.and_then(|value| {
+                        Some((value.as_str()?.to_string(), value.span().unwrap_or(0..0)))
+                    }),
+                    key_span: attr.key.span().unwrap_or(0..0),
+                    local_name,
+                }
+            })
+            .collect()
+    }
+
+    /// Source address of a provider local name; without an explicit source Terraform
+    /// assumes the `hashicorp` namespace (and its built-in `terraform` provider)
+    fn address(local_name: &str, source: Option<&str>) -> String {
+        match source {
+            Some(source) => lock_file::normalize_source(source),
+            None if local_name == "terraform" => "terraform.io/builtin/terraform".to_string(),
+            None => lock_file::normalize_source(local_name),
+        }
+    }
+
+    /// The lock file next to a configuration file, if there is one
+    fn load_lock_file(&self, uri: &str) -> Option<LockFile> {
+        let path = Module::directory_of(uri)?.join(LOCK_FILE_NAME);
+        let text = std::fs::read_to_string(&path).ok()?;
+        let document = self
+            .documents
+            .get_or_parse(&format!("file://{}", path.display()), &text);
+        Some(LockFile::from_body(document.body.as_ref()?, &text))
+    }
+
+    /// Check the `required_providers` entries of a configuration file against the lock file
+    fn check_requirements(&self, body: &Body, ctx: &mut RuleContext) {
+        let requirements = Self::requirements(body);
+        if requirements.is_empty() {
+            return;
+        }
+        // Without a lock file the module has not been initialized (or is not a root module)
+        let Some(lock) = self.load_lock_file(ctx.uri) else {
+            return;
+        };
+
+        for requirement in requirements {
+            if lock_file::is_builtin(&requirement.address) {
+                continue;
+            }
+            let Some(entry) = lock.entry(&requirement.address) else {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Provider '{}' ({}) is missing from {}; run `terraform init` to lock it",
+                        requirement.local_name, requirement.address, LOCK_FILE_NAME
+                    ),
+                )
+                .with_span(&requirement.key_span, ctx.text)
+                .with_code("MISSING_LOCK_ENTRY")
+                .report(ctx);
+                continue;
+            };
+
+            let Some((constraint, span)) = &requirement.version else {
+                continue;
+            };
+            let (Ok(constraints), Some(locked)) = (
+                VersionConstraints::parse(constraint),
+                entry.version.as_deref().and_then(Version::parse),
+            ) else {
+                continue;
+            };
+            if !constraints.matches(&locked) {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Locked version {} of provider '{}' does not satisfy the constraint \"{}\"; run `terraform init -upgrade`",
+                        locked, requirement.local_name, constraint
+                    ),
+                )
+                .with_span(span, ctx.text)
+                .with_code("LOCKED_VERSION_MISMATCH")
+                .report(ctx);
+            }
+        }
+    }
+
+    /// Providers required by a module and the local modules it calls
+    fn module_providers(&self, module: &Module) -> ModuleProviders {
+        let mut providers = ModuleProviders {
+            addresses: BTreeSet::new(),
+            complete: true,
+        };
+        let mut visited = BTreeSet::new();
+        self.collect_providers(module, &mut providers, &mut visited);
+        providers
+    }
+
+    fn collect_providers(&self, module: &Module, providers: &mut ModuleProviders, visited: &mut BTreeSet<PathBuf>) {
+        let bodies: Vec<&Body> = module
+            .files
+            .iter()
+            .filter_map(|file| file.document.body.as_ref())
+            .collect();
+        let requirements: Vec<ProviderRequirement> =
+            bodies.iter().flat_map(|body| Self::requirements(body)).collect();
+        let address_of = |local_name: &str| {
+            requirements
+                .iter()
+                .find(|r| r.local_name == local_name)
+                .map_or_else(|| Self::address(local_name, None), |r| r.address.clone())
+        };
+
+        providers
+            .addresses
+            .extend(requirements.iter().map(|r| r.address.clone()));
+
+        let blocks = bodies.iter().flat_map(|body| body.blocks()).flat_map(|block| {
+            // Scoped data sources of `check` blocks use providers too
+            let nested: Vec<&Block> = if block.has_ident("check") {
+                block.body.blocks().collect()
+            } else {
+                vec![block]
+            };
+            nested
+        });
+        for block in blocks {
+            match block.ident.as_str() {
+                // Resource types are prefixed with their provider's local name
+                "resource" | "data" | "ephemeral" => {
+                    let explicit = block
+                        .body
+                        .get_attribute("provider")
+                        .and_then(|attr| Self::provider_local_name(&attr.value));
+                    let implied = block
+                        .labels
+                        .first()
+                        .and_then(|label| label.as_str().split('_').next());
+                    if let Some(local_name) = explicit.or(implied) {
+                        providers.addresses.insert(address_of(local_name));
+                    }
+                }
+                "provider" => {
+                    if let Some(label) = block.labels.first() {
+                        providers.addresses.insert(address_of(label.as_str()));
+                    }
+                }
+                "module" => {
+                    let source = block
+                        .body
+                        .get_attribute("source")
+                        .and_then(|attr| attr.value.as_str());
+                    let child = source
+                        .filter(|source| ModuleSource::parse(source).kind == SourceKind::Local)
+                        .map(|source| Path::new(&module.index.directory).join(source));
+                    match child {
+                        Some(directory) => {
+                            let directory = directory.canonicalize().unwrap_or(directory);
+                            if visited.insert(directory.clone()) {
+                                let child = Module::load(&directory, &self.documents);
+                                self.collect_providers(&child, providers, visited);
+                            }
+                        }
+                        None => providers.complete = false,
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Local name from a `provider = aws.west` meta-argument
+    fn provider_local_name(value: &Expression) -> Option<&str> {
+        match value {
+            Expression::Variable(name) => Some(name.as_str()),
+            Expression::Traversal(traversal) => match &traversal.expr {
+                Expression::Variable(name) => Some(name.as_str()),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Check the entries of a lock file against what its module requires
+    fn check_lock_file(&self, body: &Body, ctx: &mut RuleContext) {
+        let lock = LockFile::from_body(body, ctx.text);
+        let options = self.options(ctx);
+        let min_hashes = options
+            .get("min_platform_hashes")
+            .and_then(Value::as_u64)
+            .unwrap_or_default() as usize;
+
+        let module = self.documents.module_for(ctx.uri, ctx.text);
+        let providers = self.module_providers(&module);
+        // A lock file without configuration next to it cannot be judged stale
+        let check_stale = providers.complete && !module.files.is_empty();
+
+        for entry in &lock.entries {
+            if check_stale && !providers.addresses.contains(&entry.address) {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Provider '{}' is locked but no longer required by the configuration",
+                        entry.address
+                    ),
+                )
+                .with_span(&entry.address_span, ctx.text)
+                .with_code("STALE_LOCK_ENTRY")
+                .with_suggestions(Some(vec![Self::remove_entry_fix(&entry.span, ctx.text)]))
+                .report(ctx);
+                continue;
+            }
+
+            // Only the number of hashes can be checked: `h1:` hashes are per platform
+            // package but do not say which platform they belong to
+            let platform_hashes = entry.hashes.iter().filter(|h| h.starts_with("h1:")).count();
+            if platform_hashes < min_hashes {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Provider '{}' has {} 'h1:' hash(es), fewer than min_platform_hashes ({}); run `terraform providers lock` with a -platform flag for each platform in use",
+                        entry.address, platform_hashes, min_hashes
+                    ),
+                )
+                .with_span(&entry.address_span, ctx.text)
+                .with_code("TOO_FEW_PLATFORM_HASHES")
+                .report(ctx);
+            }
+        }
+    }
+
+    /// Delete a lock entry together with the blank line that separates it from the next,
+    /// or from the previous one when it is the last
+    fn remove_entry_fix(span: &Range<usize>, text: &str) -> SuggestFix {
+        let mut start = span.start;
+        let end = text.len() - text[span.end..].trim_start_matches(['\n', '\r']).len();
+        if end == text.len() {
+            let previous = text[..span.start].trim_end_matches(['\n', '\r']).len();
+            if previous > 0 {
+                // Keep the line break that ends the previous entry
+                start = previous + if text[previous..].starts_with("\r\n") { 2 } else { 1 };
+            }
+        }
+        SuggestFix {
+            title: "Remove the stale lock entry".to_string(),
+            fix: Some(Fix {
+                range: TerraformUtils::span_to_range(&(start..end), text),
+                text: String::new(),
+            }),
+        }
+    }
+}
+
+impl Rule for ValidLockFileRule {
+    fn id(&self) -> &'static str {
+        "valid-lock-file"
+    }
+
+    fn description(&self) -> &'static str {
+        "Checks .terraform.lock.hcl against required_providers: missing and stale entries, locked versions outside their constraint and entries with fewer 'h1:' hashes than configured"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("error".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        if !self.enabled(ctx) {
+            return;
+        }
+
+        let document = self.documents.get_or_parse(ctx.uri, ctx.text);
+        let Some(body) = &document.body else {
+            return;
+        };
+        if LockFile::is_lock_file(ctx.uri) {
+            self.check_lock_file(body, ctx);
+        } else if !TerraformUtils::is_tfvars(ctx.uri) {
+            self.check_requirements(body, ctx);
+        }
+    }
+}
+
+impl TerraformRule for ValidLockFileRule {
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        vec![
+            OptionSpec::new(
+                "min_platform_hashes",
+                "Minimum number of 'h1:' package hashes per lock entry, usually the number of platforms Terraform runs on; hashes do not name their platform, so only their number is checked. 0 disables the check",
+                ConfigType::Integer,
+                json!(0),
+            )
+            .with_range(Some(0.0), None),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{codes, run};
+
+    #[test]
+    fn check_blocks_use_providers_and_hashes_are_counted() {
+        let directory = std::env::temp_dir().join(format!("forseti-lock-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join("main.tf"),
+            r#"resource "aws_s3_bucket" "b" {}
+
+check "health" {
+  data "http" "site" {
+    url = "https://example.com"
+  }
+}
+"#,
+        )
+        .unwrap();
+        let lock = r#"provider "registry.terraform.io/hashicorp/aws" {
+  version = "5.0.0"
+  hashes = ["h1:one", "h1:two", "zh:three"]
+}
+
+provider "registry.terraform.io/hashicorp/http" {
+  version = "3.4.0"
+  hashes = ["h1:one", "zh:two"]
+}
+
+provider "registry.terraform.io/hashicorp/null" {
+  version = "3.2.0"
+  hashes = ["h1:one", "h1:two"]
+}
+"#;
+        let uri = format!("file://{}", directory.join(LOCK_FILE_NAME).display());
+        let rule = ValidLockFileRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, &uri, lock, json!(["error", { "min_platform_hashes": 2 }]));
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(codes(&diagnostics), ["TOO_FEW_PLATFORM_HASHES", "STALE_LOCK_ENTRY"]);
+        assert!(diagnostics[0].message.contains("hashicorp/http"));
+        assert!(diagnostics[1].message.contains("hashicorp/null"));
+    }
+
+    #[test]
+    fn requirements_must_be_locked_within_their_constraint() {
+        let directory = std::env::temp_dir().join(format!("forseti-lock-req-{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(
+            directory.join(LOCK_FILE_NAME),
+            "provider \"registry.terraform.io/hashicorp/aws\" {\n  version = \"5.31.0\"\n}\n",
+        )
+        .unwrap();
+        let text = r#"terraform {
+  required_providers {
+    aws = {
+      source  = "hashicorp/aws"
+      version = "~> 4.0"
+    }
+    random = {
+      source = "hashicorp/random"
+    }
+  }
+}
+"#;
+        let uri = format!("file://{}", directory.join("versions.tf").display());
+        let rule = ValidLockFileRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, &uri, text, json!("error"));
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(codes(&diagnostics), ["LOCKED_VERSION_MISMATCH", "MISSING_LOCK_ENTRY"]);
+        assert_eq!(
+            diagnostics[0].message,
+            "Locked version 5.31.0 of provider 'aws' does not satisfy the constraint \"~> 4.0\"; run `terraform init -upgrade`"
+        );
+        assert_eq!(diagnostics[0].range.start.line, 4);
+        assert!(diagnostics[1].message.starts_with("Provider 'random' (registry.terraform.io/hashicorp/random) is missing"));
+    }
+
+    #[test]
+    fn providers_of_local_child_modules_are_required() {
+        let directory = std::env::temp_dir().join(format!("forseti-lock-child-{}", std::process::id()));
+        std::fs::create_dir_all(directory.join("modules/network")).unwrap();
+        std::fs::write(directory.join("modules/network/main.tf"), "resource \"aws_vpc\" \"main\" {}\n").unwrap();
+        std::fs::write(
+            directory.join("main.tf"),
+            "module \"network\" {\n  source = \"./modules/network\"\n}\n",
+        )
+        .unwrap();
+        let lock = r#"provider "registry.terraform.io/hashicorp/aws" {
+  version = "5.31.0"
+}
+
+provider "registry.terraform.io/hashicorp/google" {
+  version = "5.10.0"
+}
+"#;
+        let uri = format!("file://{}", directory.join(LOCK_FILE_NAME).display());
+        let rule = ValidLockFileRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, &uri, lock, json!("error"));
+        assert_eq!(codes(&diagnostics), ["STALE_LOCK_ENTRY"]);
+        assert!(diagnostics[0].message.contains("hashicorp/google"));
+        assert_eq!(
+            crate::testing::apply_fix(lock, &diagnostics[0]).unwrap(),
+            "provider \"registry.terraform.io/hashicorp/aws\" {\n  version = \"5.31.0\"\n}\n"
+        );
+
+        // A module that cannot be followed may need any provider
+        std::fs::write(
+            directory.join("dns.tf"),
+            "module \"dns\" {\n  source  = \"terraform-google-modules/cloud-dns/google\"\n  version = \"5.0.0\"\n}\n",
+        )
+        .unwrap();
+        let rule = ValidLockFileRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, &uri, lock, json!("error"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        assert!(diagnostics.is_empty());
+    }
+}
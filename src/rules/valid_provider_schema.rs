@@ -0,0 +1,501 @@
+use forseti_sdk::core::{ConfigType, Fix, SuggestFix};
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::structure::{Block, Body};
+use serde_json::json;
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::module::Module;
+use crate::options::{OptionSpec, TerraformRule};
+use crate::provider_schema::{BlockSchema, ProviderSchemas};
+use crate::utils::{HclRule, TerraformUtils};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Arguments Terraform itself handles on every resource and data block
+const META_ARGUMENTS: [&str; 4] = ["count", "for_each", "provider", "depends_on"];
+/// Nested blocks Terraform itself handles on resource and data blocks
+const META_BLOCKS: [&str; 3] = ["lifecycle", "connection", "provisioner"];
+
+struct CachedSchemas {
+    modified: Option<SystemTime>,
+    schemas: Arc<ProviderSchemas>,
+}
+
+pub struct ValidProviderSchemaRule {
+    documents: Arc<DocumentCache>,
+    /// Loaded schema files by path, reloaded when the file changes
+    schemas: Mutex<HashMap<PathBuf, CachedSchemas>>,
+}
+
+impl ValidProviderSchemaRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self {
+            documents,
+            schemas: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Relative schema paths are looked up from the checked file's directory upwards,
+    /// so a schema checked in at the repository root applies to every module below it
+    fn resolve_path(uri: &str, configured: &str) -> PathBuf {
+        let path = Path::new(configured);
+        if path.is_absolute() {
+            return path.to_path_buf();
+        }
+        Module::directory_of(uri)
+            .and_then(|directory| {
+                directory
+                    .ancestors()
+                    .map(|ancestor| ancestor.join(path))
+                    .find(|candidate| candidate.is_file())
+            })
+            .unwrap_or_else(|| path.to_path_buf())
+    }
+
+    fn load(&self, path: &Path) -> Result<Arc<ProviderSchemas>, String> {
+        let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let mut schemas = self.schemas.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = schemas.get(path)
+            && cached.modified == modified
+        {
+            return Ok(Arc::clone(&cached.schemas));
+        }
+
+        let loaded = Arc::new(ProviderSchemas::load(path)?);
+        schemas.insert(
+            path.to_path_buf(),
+            CachedSchemas {
+                modified,
+                schemas: Arc::clone(&loaded),
+            },
+        );
+        Ok(loaded)
+    }
+
+    fn rename_fix(span: &Range<usize>, replacement: &str, text: &str) -> Option<Vec<SuggestFix>> {
+        Some(vec![SuggestFix {
+            title: format!("Replace with `{}`", replacement),
+            fix: Some(Fix {
+                range: TerraformUtils::span_to_range(span, text),
+                text: replacement.to_string(),
+            }),
+        }])
+    }
+
+    /// Message suffix and fix for a misspelled name
+    fn suggestion<'a>(
+        name: &str,
+        candidates: impl IntoIterator<Item = &'a str>,
+        span: &Range<usize>,
+        text: &str,
+    ) -> (String, Option<Vec<SuggestFix>>) {
+        match TerraformUtils::closest_match(name, candidates) {
+            Some(suggestion) => (
+                format!(". Did you mean '{}'?", suggestion),
+                Self::rename_fix(span, suggestion, text),
+            ),
+            None => (String::new(), None),
+        }
+    }
+
+    fn check_resource(&self, block: &Block, schemas: &ProviderSchemas, ctx: &mut RuleContext) {
+        let kind = block.ident.as_str();
+        let Some(type_label) = block.labels.first() else {
+            return;
+        };
+        let type_name = type_label.as_str();
+        let type_span = TerraformUtils::label_span(type_label, ctx.text);
+        let description = match kind {
+            "resource" => "resource type",
+            "data" => "data source type",
+            _ => "ephemeral resource type",
+        };
+
+        let Some((_, schema)) = schemas.types(kind).find(|(name, _)| *name == type_name) else {
+            // Only types of providers present in the schema file can be judged
+            let prefix = |name: &str| name.split('_').next().unwrap_or_default().to_string();
+            if !schemas.types(kind).any(|(name, _)| prefix(name) == prefix(type_name)) {
+                return;
+            }
+            let (hint, fix) = Self::suggestion(
+                type_name,
+                schemas.types(kind).map(|(name, _)| name.as_str()),
+                &type_span,
+                ctx.text,
+            );
+            DiagnosticBuilder::new(self.id(), format!("Unknown {} '{}'{}", description, type_name, hint))
+                .with_span(&type_span, ctx.text)
+                .with_code("UNKNOWN_RESOURCE_TYPE")
+                .with_suggestions(fix)
+                .report(ctx);
+            return;
+        };
+
+        if schema.block.deprecated {
+            DiagnosticBuilder::new(
+                self.id(),
+                format!("The {} '{}' is deprecated by its provider", description, type_name),
+            )
+            .with_span(&type_span, ctx.text)
+            .with_code("DEPRECATED_RESOURCE")
+            .report(ctx);
+        }
+
+        self.check_body(&block.body, &schema.block, true, &type_span, ctx);
+    }
+
+    /// Check a block body against its schema; `top_level` allows Terraform's meta-arguments
+    /// and `anchor` locates diagnostics about missing content
+    fn check_body(
+        &self,
+        body: &Body,
+        schema: &BlockSchema,
+        top_level: bool,
+        anchor: &Range<usize>,
+        ctx: &mut RuleContext,
+    ) {
+        // JSON syntax writes nested blocks as object-valued properties
+        let json_syntax = TerraformUtils::is_json_syntax(ctx.uri);
+
+        for attr in body.attributes() {
+            let name = attr.key.as_str();
+            let span = attr.key.span().unwrap_or(0..0);
+            if top_level && META_ARGUMENTS.contains(&name) {
+                continue;
+            }
+
+            if let Some(attribute) = schema.attributes.get(name) {
+                if attribute.is_read_only() {
+                    DiagnosticBuilder::new(
+                        self.id(),
+                        format!("Argument '{}' is computed by the provider and cannot be set", name),
+                    )
+                    .with_span(&span, ctx.text)
+                    .with_code("READ_ONLY_ARGUMENT")
+                    .report(ctx);
+                } else if attribute.deprecated {
+                    DiagnosticBuilder::new(self.id(), format!("Argument '{}' is deprecated by its provider", name))
+                        .with_span(&span, ctx.text)
+                        .with_code("DEPRECATED_ARGUMENT")
+                        .report(ctx);
+                }
+                continue;
+            }
+
+            if schema.block_types.contains_key(name) {
+                if !json_syntax {
+                    DiagnosticBuilder::new(
+                        self.id(),
+                        format!("'{}' is a nested block, not an argument; write it as `{} {{ ... }}`", name, name),
+                    )
+                    .with_span(&span, ctx.text)
+                    .with_code("UNKNOWN_ARGUMENT")
+                    .report(ctx);
+                }
+                continue;
+            }
+
+            let settable = schema
+                .attributes
+                .iter()
+                .filter(|(_, attribute)| !attribute.is_read_only())
+                .map(|(name, _)| name.as_str());
+            let (hint, fix) = Self::suggestion(name, settable, &span, ctx.text);
+            DiagnosticBuilder::new(self.id(), format!("Unknown argument '{}'{}", name, hint))
+                .with_span(&span, ctx.text)
+                .with_code("UNKNOWN_ARGUMENT")
+                .with_suggestions(fix)
+                .report(ctx);
+        }
+
+        for block in body.blocks() {
+            let ident = block.ident.as_str();
+            if top_level && META_BLOCKS.contains(&ident) {
+                continue;
+            }
+
+            // `dynamic "name"` generates `name` blocks from its `content`
+            let (name, span, content) = if ident == "dynamic" {
+                let Some(label) = block.labels.first() else {
+                    continue;
+                };
+                let content = block.body.blocks().find(|b| b.has_ident("content"));
+                (label.as_str(), TerraformUtils::label_span(label, ctx.text), content.map(|b| &b.body))
+            } else {
+                (ident, block.ident.span().unwrap_or(0..0), Some(&block.body))
+            };
+
+            let Some(nested) = schema.block_types.get(name) else {
+                let (message, fix) = if schema.attributes.contains_key(name) {
+                    (
+                        format!("'{}' is an argument, not a nested block; write it as `{} = ...`", name, name),
+                        None,
+                    )
+                } else {
+                    let (hint, fix) = Self::suggestion(
+                        name,
+                        schema.block_types.keys().map(String::as_str),
+                        &span,
+                        ctx.text,
+                    );
+                    (format!("Unknown nested block '{}'{}", name, hint), fix)
+                };
+                DiagnosticBuilder::new(self.id(), message)
+                    .with_span(&span, ctx.text)
+                    .with_code("UNKNOWN_BLOCK")
+                    .with_suggestions(fix)
+                    .report(ctx);
+                continue;
+            };
+
+            if nested.block.deprecated {
+                DiagnosticBuilder::new(self.id(), format!("Nested block '{}' is deprecated by its provider", name))
+                    .with_span(&span, ctx.text)
+                    .with_code("DEPRECATED_ARGUMENT")
+                    .report(ctx);
+            }
+            if let Some(content) = content {
+                self.check_body(content, &nested.block, false, &span, ctx);
+            }
+        }
+
+        self.check_required(body, schema, json_syntax, anchor, ctx);
+    }
+
+    fn check_required(
+        &self,
+        body: &Body,
+        schema: &BlockSchema,
+        json_syntax: bool,
+        anchor: &Range<usize>,
+        ctx: &mut RuleContext,
+    ) {
+        let has_block = |name: &str| {
+            body.blocks().any(|block| {
+                block.has_ident(name)
+                    || (block.has_ident("dynamic") && block.labels.first().is_some_and(|l| l.as_str() == name))
+            }) || (json_syntax && body.has_attribute(name))
+        };
+
+        let missing_attributes = schema
+            .attributes
+            .iter()
+            .filter(|(name, attribute)| attribute.required && !body.has_attribute(name))
+            .map(|(name, _)| format!("argument '{}'", name));
+        let missing_blocks = schema
+            .block_types
+            .iter()
+            .filter(|(name, nested)| nested.min_items > 0 && !has_block(name))
+            .map(|(name, _)| format!("block '{}'", name));
+
+        for missing in missing_attributes.chain(missing_blocks).collect::<Vec<_>>() {
+            DiagnosticBuilder::new(self.id(), format!("Missing required {}", missing))
+                .with_span(anchor, ctx.text)
+                .with_code("MISSING_REQUIRED_ARGUMENT")
+                .report(ctx);
+        }
+    }
+}
+
+impl Rule for ValidProviderSchemaRule {
+    fn id(&self) -> &'static str {
+        "valid-provider-schema"
+    }
+
+    fn description(&self) -> &'static str {
+        "Validates resource and data blocks against an offline provider schema (terraform providers schema -json): unknown types, arguments and blocks, missing required arguments and deprecated arguments"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("error".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for ValidProviderSchemaRule {
+    fn option_specs(&self) -> Vec<OptionSpec> {
+        vec![OptionSpec::new(
+            "schema_file",
+            "Path to the output of `terraform providers schema -json`; relative paths are searched from each file's directory upwards. Empty disables the rule",
+            ConfigType::String,
+            json!(""),
+        )]
+    }
+}
+
+impl HclRule for ValidProviderSchemaRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        let options = self.options(ctx);
+        let configured = options.str("schema_file");
+        if configured.is_empty() || !Module::is_configuration_file(Path::new(ctx.uri)) {
+            return;
+        }
+
+        let schemas = match self.load(&Self::resolve_path(ctx.uri, configured)) {
+            Ok(schemas) => schemas,
+            Err(error) => {
+                self.report_invalid_config(ctx, &error);
+                return;
+            }
+        };
+
+        let blocks = body.blocks().flat_map(|block| {
+            // Scoped data sources of `check` blocks are validated too
+            let nested: Vec<&Block> = if block.has_ident("check") {
+                block.body.blocks().collect()
+            } else {
+                vec![block]
+            };
+            nested
+        });
+        for block in blocks {
+            if matches!(block.ident.as_str(), "resource" | "data" | "ephemeral") {
+                self.check_resource(block, &schemas, ctx);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{apply_fix, codes, run};
+    use forseti_sdk::core::Diagnostic;
+
+    const SCHEMA: &str = r#"{
+  "provider_schemas": {
+    "registry.terraform.io/hashicorp/aws": {
+      "resource_schemas": {
+        "aws_instance": {
+          "block": {
+            "attributes": {
+              "ami": { "type": "string", "required": true },
+              "instance_type": { "type": "string", "optional": true },
+              "arn": { "type": "string", "computed": true }
+            },
+            "block_types": {
+              "ebs_block_device": {
+                "nesting_mode": "set",
+                "block": { "attributes": { "device_name": { "type": "string", "required": true } } }
+              }
+            }
+          }
+        }
+      }
+    }
+  }
+}"#;
+
+    /// Check `text` as `uri` against a schema file holding `schema`
+    fn check(name: &str, schema: &str, uri: &str, text: &str) -> Vec<Diagnostic> {
+        let directory = std::env::temp_dir().join(format!("forseti-schema-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("schema.json");
+        std::fs::write(&path, schema).unwrap();
+        let rule = ValidProviderSchemaRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, uri, text, json!(["error", { "schema_file": path }]));
+        std::fs::remove_dir_all(&directory).unwrap();
+        diagnostics
+    }
+
+    #[test]
+    fn unknown_resource_types_are_suggested() {
+        let text = r#"resource "aws_instanse" "web" {}
+
+resource "google_compute_instance" "web" {}
+"#;
+        let diagnostics = check("type", SCHEMA, "file:///main.tf", text);
+        // Types of providers missing from the schema cannot be judged
+        assert_eq!(codes(&diagnostics), ["UNKNOWN_RESOURCE_TYPE"]);
+        assert_eq!(
+            diagnostics[0].message,
+            "Unknown resource type 'aws_instanse'. Did you mean 'aws_instance'?"
+        );
+        let fixed = apply_fix(text, &diagnostics[0]).unwrap();
+        assert!(fixed.starts_with(r#"resource "aws_instance" "web" {}"#));
+    }
+
+    #[test]
+    fn unknown_and_read_only_arguments_are_reported() {
+        let text = r#"resource "aws_instance" "web" {
+  ami           = "ami-123"
+  instanse_type = "t3.micro"
+  arn           = "arn:aws:ec2:::instance/i-1"
+  count         = 2
+}
+"#;
+        let diagnostics = check("argument", SCHEMA, "file:///main.tf", text);
+        assert_eq!(codes(&diagnostics), ["UNKNOWN_ARGUMENT", "READ_ONLY_ARGUMENT"]);
+        assert_eq!(
+            diagnostics[0].message,
+            "Unknown argument 'instanse_type'. Did you mean 'instance_type'?"
+        );
+        assert!(apply_fix(text, &diagnostics[0]).unwrap().contains("  instance_type = \"t3.micro\""));
+    }
+
+    #[test]
+    fn missing_required_arguments_are_reported_on_the_type() {
+        let text = r#"resource "aws_instance" "web" {
+  instance_type = "t3.micro"
+
+  ebs_block_device {
+    volume_size = 10
+  }
+}
+"#;
+        let diagnostics = check("required", SCHEMA, "file:///main.tf", text);
+        assert_eq!(
+            codes(&diagnostics),
+            ["UNKNOWN_ARGUMENT", "MISSING_REQUIRED_ARGUMENT", "MISSING_REQUIRED_ARGUMENT"]
+        );
+        assert_eq!(diagnostics[1].message, "Missing required argument 'device_name'");
+        assert_eq!(diagnostics[2].message, "Missing required argument 'ami'");
+        assert_eq!(diagnostics[2].range.start.line, 0);
+        assert_eq!(diagnostics[2].range.start.character, 10);
+    }
+
+    #[test]
+    fn unreadable_schema_files_are_configuration_errors() {
+        let text = "resource \"aws_instanse\" \"web\" {}\n";
+        let invalid = check("invalid", "{ not json", "file:///main.tf", text);
+        assert_eq!(codes(&invalid), ["INVALID_OPTIONS"]);
+        assert!(invalid[0].message.contains("invalid provider schema file"));
+
+        let missing = std::env::temp_dir().join(format!("forseti-schema-none-{}.json", std::process::id()));
+        let rule = ValidProviderSchemaRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, "file:///main.tf", text, json!(["error", { "schema_file": missing }]));
+        assert_eq!(codes(&diagnostics), ["INVALID_OPTIONS"]);
+        assert!(diagnostics[0].message.contains("cannot read provider schema file"));
+    }
+
+    #[test]
+    fn json_syntax_writes_nested_blocks_as_properties() {
+        let text = r#"{
+  "resource": {
+    "aws_instance": {
+      "web": {
+        "instanse_type": "t3.micro",
+        "ebs_block_device": [{ "device_name": "/dev/sdb" }]
+      }
+    }
+  }
+}
+"#;
+        let diagnostics = check("json", SCHEMA, "file:///main.tf.json", text);
+        assert_eq!(codes(&diagnostics), ["UNKNOWN_ARGUMENT", "MISSING_REQUIRED_ARGUMENT"]);
+        assert!(diagnostics[0].message.starts_with("Unknown argument 'instanse_type'"));
+        assert_eq!(diagnostics[1].message, "Missing required argument 'ami'");
+    }
+}
@@ -0,0 +1,355 @@
+use forseti_sdk::core::{Fix, Position, Range, SuggestFix};
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::expr::{Expression, ObjectKey};
+use hcl::edit::structure::Body;
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::module::Module;
+use crate::options::TerraformRule;
+use crate::types::{self, TypeConstraint};
+use crate::utils::{HclRule, TerraformUtils};
+use std::path::Path;
+use std::sync::Arc;
+
+/// What the module declares about one input variable
+struct VariableInfo {
+    name: String,
+    /// Parsed `type` argument; None when omitted or not understood
+    constraint: Option<TypeConstraint>,
+    required: bool,
+}
+
+pub struct ValidTfvarsRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl ValidTfvarsRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+
+    /// Terraform loads `terraform.tfvars` and `*.auto.tfvars` (or their `.json`
+    /// forms) without being asked
+    fn is_auto_loaded(uri: &str) -> bool {
+        let name = uri.strip_suffix(".json").unwrap_or(uri);
+        name.ends_with("/terraform.tfvars") || name.ends_with(".auto.tfvars")
+    }
+
+    fn variables(module: &Module) -> Vec<VariableInfo> {
+        module
+            .files
+            .iter()
+            .filter_map(|file| file.document.body.as_ref())
+            .flat_map(|body| body.blocks())
+            .filter(|block| block.has_ident("variable"))
+            .filter_map(|block| {
+                let label = TerraformUtils::get_block_name(block, "variable")?;
+                Some(VariableInfo {
+                    name: label.as_str().to_string(),
+                    constraint: block
+                        .body
+                        .get_attribute("type")
+                        .and_then(|attr| TypeConstraint::parse(&attr.value)),
+                    required: !block.body.has_attribute("default"),
+                })
+            })
+            .collect()
+    }
+
+    /// The first part of a value that is not a literal, if any
+    fn non_literal(value: &Expression) -> Option<&Expression> {
+        match value {
+            Expression::Null(_) | Expression::Bool(_) | Expression::Number(_) | Expression::String(_) => None,
+            Expression::UnaryOp(op) if matches!(op.expr, Expression::Number(_)) => None,
+            Expression::Parenthesis(inner) => Self::non_literal(inner.inner()),
+            Expression::Array(items) => items.iter().find_map(Self::non_literal),
+            Expression::Object(object) => object.iter().find_map(|(key, value)| match key {
+                ObjectKey::Ident(_) | ObjectKey::Expression(Expression::String(_)) => {
+                    Self::non_literal(value.expr())
+                }
+                ObjectKey::Expression(key) => Some(key),
+            }),
+            value if types::is_literal_string(value) => None,
+            value => Some(value),
+        }
+    }
+
+    /// Names assigned by the tfvars files Terraform would load together with this one:
+    /// itself plus every auto-loaded file of the directory. Returns the file names too,
+    /// for messages.
+    fn assigned_names(&self, uri: &str, body: &Body) -> (Vec<String>, Vec<String>) {
+        let mut names: Vec<String> = body.attributes().map(|a| a.key.as_str().to_string()).collect();
+        let mut files = vec![Self::file_name(uri).to_string()];
+
+        for (other_uri, path) in Self::auto_loaded_files(uri) {
+            if other_uri == uri {
+                continue;
+            }
+            let Ok(text) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            if let Some(other) = &self.documents.get_or_parse(&other_uri, &text).body {
+                names.extend(other.attributes().map(|a| a.key.as_str().to_string()));
+            }
+            files.push(Self::file_name(&other_uri).to_string());
+        }
+
+        (names, files)
+    }
+
+    /// Auto-loaded tfvars files next to `uri`, in Terraform's load order
+    fn auto_loaded_files(uri: &str) -> Vec<(String, std::path::PathBuf)> {
+        let Some(directory) = Module::directory_of(uri) else {
+            return vec![];
+        };
+        let mut files: Vec<(String, std::path::PathBuf)> = std::fs::read_dir(&directory)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .map(|path| (format!("file://{}", path.display()), path))
+            .filter(|(uri, path)| path.is_file() && Self::is_auto_loaded(uri))
+            .collect();
+        // terraform.tfvars (then its JSON form) first, then *.auto.tfvars in name order
+        files.sort_by_key(|(uri, _)| (!uri.contains("/terraform.tfvars"), uri.clone()));
+        files
+    }
+
+    fn file_name(uri: &str) -> &str {
+        Path::new(uri).file_name().and_then(|n| n.to_str()).unwrap_or(uri)
+    }
+
+    /// Missing required variables are reported once per tfvars set: by every explicit
+    /// `-var-file` candidate, and by the first auto-loaded file for the auto-loaded set
+    fn reports_missing_variables(uri: &str) -> bool {
+        if !Self::is_auto_loaded(uri) {
+            return true;
+        }
+        Self::auto_loaded_files(uri)
+            .first()
+            .is_none_or(|(first, _)| first == uri)
+    }
+
+    fn check_missing_variables(&self, variables: &[VariableInfo], body: &Body, ctx: &mut RuleContext) {
+        if !Self::reports_missing_variables(ctx.uri) {
+            return;
+        }
+        let (names, files) = self.assigned_names(ctx.uri, body);
+        let start = Position { line: 0, character: 0 };
+
+        for variable in variables.iter().filter(|v| v.required) {
+            if names.contains(&variable.name) {
+                continue;
+            }
+            DiagnosticBuilder::new(
+                self.id(),
+                format!(
+                    "Required variable '{}' has no default and is not set in {}",
+                    variable.name,
+                    files.join(", ")
+                ),
+            )
+            .with_range(Range { start, end: start })
+            .with_code("MISSING_REQUIRED_VARIABLE")
+            .report(ctx);
+        }
+    }
+}
+
+impl Rule for ValidTfvarsRule {
+    fn id(&self) -> &'static str {
+        "valid-tfvars"
+    }
+
+    fn description(&self) -> &'static str {
+        "Checks tfvars assignments against the module's variable declarations: unknown names, type mismatches, missing required variables and non-literal values"
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        serde_json::Value::String("error".to_string())
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        // Use the HclRule trait's default implementation
+        HclRule::check(self, ctx);
+    }
+}
+
+impl TerraformRule for ValidTfvarsRule {}
+
+impl HclRule for ValidTfvarsRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
+    fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
+        if !TerraformUtils::is_tfvars(ctx.uri) {
+            return;
+        }
+
+        let module = self.documents.module_for(ctx.uri, ctx.text);
+        let variables = Self::variables(&module);
+        // A tfvars file without configuration next to it is meant for a module
+        // elsewhere, so only its own syntax can be checked
+        let has_module = !variables.is_empty();
+
+        for attr in body.attributes() {
+            let name = attr.key.as_str();
+
+            if let Some(expr) = Self::non_literal(&attr.value) {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Value of '{}' must be a literal; tfvars files cannot contain references, function calls or interpolation",
+                        name
+                    ),
+                )
+                .with_span(&expr.span().unwrap_or(0..0), ctx.text)
+                .with_code("NON_LITERAL_VALUE")
+                .report(ctx);
+                continue;
+            }
+            if !has_module {
+                continue;
+            }
+
+            let Some(variable) = variables.iter().find(|v| v.name == name) else {
+                let key_span = attr.key.span().unwrap_or(0..0);
+                let suggestion =
+                    TerraformUtils::closest_match(name, variables.iter().map(|v| v.name.as_str()));
+                let message = match suggestion {
+                    Some(suggestion) => format!(
+                        "Variable '{}' is not declared in the module. Did you mean '{}'?",
+                        name, suggestion
+                    ),
+                    None => format!("Variable '{}' is not declared in the module", name),
+                };
+                let fix = suggestion.map(|suggestion| {
+                    vec![SuggestFix {
+                        title: format!("Rename to `{}`", suggestion),
+                        fix: Some(Fix {
+                            range: TerraformUtils::span_to_range(&key_span, ctx.text),
+                            text: suggestion.to_string(),
+                        }),
+                    }]
+                });
+                DiagnosticBuilder::new(self.id(), message)
+                    .with_span(&key_span, ctx.text)
+                    .with_code("UNKNOWN_VARIABLE")
+                    .with_suggestions(fix)
+                    .report(ctx);
+                continue;
+            };
+
+            if let Some(constraint) = &variable.constraint
+                && let Err(reason) = constraint.check(&attr.value)
+            {
+                DiagnosticBuilder::new(
+                    self.id(),
+                    format!(
+                        "Value of '{}' does not match its declared type {}: {}",
+                        name, constraint, reason
+                    ),
+                )
+                .with_span(&attr.value.span().unwrap_or(0..0), ctx.text)
+                .with_code("TYPE_MISMATCH")
+                .report(ctx);
+            }
+        }
+
+        if has_module {
+            self.check_missing_variables(&variables, body, ctx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{apply_fix, codes, run};
+    use forseti_sdk::core::Diagnostic;
+    use serde_json::json;
+
+    const VARIABLES: &str = r#"variable "region" {
+  type    = string
+  default = "eu-west-1"
+}
+
+variable "instances" {
+  type    = number
+  default = 1
+}
+
+variable "tags" {
+  type    = map(string)
+  default = {}
+}
+"#;
+
+    /// Check the tfvars file `name` of a module declaring [`VARIABLES`]
+    fn check(name: &str, text: &str) -> Vec<Diagnostic> {
+        let directory = std::env::temp_dir().join(format!("forseti-tfvars-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("variables.tf"), VARIABLES).unwrap();
+        std::fs::write(directory.join(name), text).unwrap();
+        let uri = format!("file://{}", directory.join(name).display());
+        let rule = ValidTfvarsRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, &uri, text, json!("error"));
+        std::fs::remove_dir_all(&directory).unwrap();
+        diagnostics
+    }
+
+    #[test]
+    fn undeclared_variables_are_suggested() {
+        let text = "regoin = \"us-east-1\"\nzone = \"a\"\n";
+        let diagnostics = check("prod.tfvars", text);
+        assert_eq!(codes(&diagnostics), ["UNKNOWN_VARIABLE", "UNKNOWN_VARIABLE"]);
+        assert_eq!(
+            diagnostics[0].message,
+            "Variable 'regoin' is not declared in the module. Did you mean 'region'?"
+        );
+        assert_eq!(diagnostics[1].message, "Variable 'zone' is not declared in the module");
+        assert_eq!(
+            apply_fix(text, &diagnostics[0]).unwrap(),
+            "region = \"us-east-1\"\nzone = \"a\"\n"
+        );
+    }
+
+    #[test]
+    fn values_must_be_literals() {
+        let text = r#"region    = var.default_region
+instances = -2
+tags      = { Name = "web-${terraform.workspace}", Team = "platform" }
+"#;
+        let diagnostics = check("literal.tfvars", text);
+        assert_eq!(codes(&diagnostics), ["NON_LITERAL_VALUE", "NON_LITERAL_VALUE"]);
+        assert!(diagnostics[0].message.starts_with("Value of 'region' must be a literal"));
+        assert_eq!(diagnostics[1].range.start.line, 2);
+        assert_eq!(diagnostics[1].range.start.character, 21);
+    }
+
+    #[test]
+    fn values_must_match_the_declared_type() {
+        let text = "region = \"us-east-1\"\ninstances = \"three\"\ntags = { Name = [\"web\"] }\n";
+        let diagnostics = check("types.tfvars", text);
+        assert_eq!(codes(&diagnostics), ["TYPE_MISMATCH", "TYPE_MISMATCH"]);
+        assert!(diagnostics[0].message.starts_with("Value of 'instances' does not match its declared type number"));
+        assert!(diagnostics[1].message.starts_with("Value of 'tags' does not match its declared type map(string)"));
+    }
+
+    #[test]
+    fn json_variables_files_are_checked() {
+        let text = r#"{
+  "regoin": "us-east-1",
+  "instances": "three",
+  "tags": { "Name": "${var.name}" }
+}
+"#;
+        // Strings in JSON variables files are never templates, so `${` is literal text
+        let diagnostics = check("prod.tfvars.json", text);
+        assert_eq!(codes(&diagnostics), ["UNKNOWN_VARIABLE", "TYPE_MISMATCH"]);
+        assert!(diagnostics[0].message.ends_with("Did you mean 'region'?"));
+        assert_eq!(diagnostics[0].range.start.line, 1);
+        assert!(diagnostics[1].message.starts_with("Value of 'instances'"));
+    }
+}
@@ -1,8 +1,19 @@
 use forseti_sdk::ruleset::{Rule, RuleContext};
-use hcl::Body;
+use hcl::edit::structure::Body;
+use crate::cache::DocumentCache;
+use crate::options::TerraformRule;
 use crate::utils::{HclRule, TerraformUtils};
+use std::sync::Arc;
 
-pub struct VariableDescriptionRequiredRule;
+pub struct VariableDescriptionRequiredRule {
+    documents: Arc<DocumentCache>,
+}
+
+impl VariableDescriptionRequiredRule {
+    pub fn new(documents: Arc<DocumentCache>) -> Self {
+        Self { documents }
+    }
+}
 
 impl Rule for VariableDescriptionRequiredRule {
     fn id(&self) -> &'static str {
@@ -23,22 +34,53 @@ impl Rule for VariableDescriptionRequiredRule {
     }
 }
 
+impl TerraformRule for VariableDescriptionRequiredRule {}
+
 impl HclRule for VariableDescriptionRequiredRule {
+    fn documents(&self) -> &DocumentCache {
+        &self.documents
+    }
+
     fn check_hcl(&self, body: &Body, ctx: &mut RuleContext) {
         for block in body.blocks() {
-            if block.identifier() == "variable" {
-                if let Some(variable_name) = TerraformUtils::get_block_name(block, "variable") {
-                    if !TerraformUtils::has_description_attribute(block) {
-                        let diagnostic = TerraformUtils::create_missing_description_diagnostic(
-                            self.id(),
-                            "variable",
-                            &variable_name,
-                            ctx.text,
-                        );
-                        ctx.report(diagnostic);
-                    }
-                }
+            if block.has_ident("variable")
+                && let Some(label) = TerraformUtils::get_block_name(block, "variable")
+                && !TerraformUtils::has_description_attribute(block)
+            {
+                TerraformUtils::create_missing_description_diagnostic(
+                    self.id(),
+                    "variable",
+                    block,
+                    label,
+                    ctx.text,
+                )
+                .report(ctx);
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{covered, run};
+    use serde_json::json;
+
+    #[test]
+    fn missing_descriptions_are_reported_on_the_label() {
+        let text = r#"# variable "region" is set per environment
+variable "zone" {
+  description = "Zone, defaults to the first of region"
+}
+
+variable "region" {
+  type = string
+}
+"#;
+        let rule = VariableDescriptionRequiredRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, "file:///variables.tf", text, json!("error"));
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].range.start.line, 5);
+        assert_eq!(covered(text, &diagnostics[0]), "region");
+    }
+}
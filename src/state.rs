@@ -0,0 +1,224 @@
+//! Terraform state files (`*.tfstate`), read only to find the values they store in
+//! plain text and where.
+
+use crate::json::{JsonKind, JsonValue};
+use std::ops::Range;
+
+/// A string value stored in state
+pub struct StateString<'a> {
+    /// Resource or output address followed by the attribute path, e.g.
+    /// `aws_db_instance.main.password`; list indexes are left out
+    pub address: String,
+    /// Location in the document, e.g. `$.resources[0].instances[0].attributes.password`
+    pub json_path: String,
+    /// Innermost object key the value is stored under
+    pub key: String,
+    pub value: &'a str,
+    /// Span of the value's content, without the quotes
+    pub span: Range<usize>,
+    /// Whether Terraform marked the value sensitive
+    pub sensitive: bool,
+}
+
+/// Where a value sits, tracked while walking down from an instance or output
+#[derive(Clone)]
+struct Location {
+    address: String,
+    json_path: String,
+    key: String,
+    /// Attribute path as in `sensitive_attributes`: object keys and list indexes
+    steps: Vec<String>,
+}
+
+impl Location {
+    fn member(&self, key: &str) -> Self {
+        let mut steps = self.steps.clone();
+        steps.push(key.to_string());
+        let identifier = key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        Self {
+            address: format!("{}.{}", self.address, key),
+            json_path: if identifier {
+                format!("{}.{}", self.json_path, key)
+            } else {
+                format!("{}[{:?}]", self.json_path, key)
+            },
+            key: key.to_string(),
+            steps,
+        }
+    }
+
+    fn item(&self, index: usize) -> Self {
+        let mut steps = self.steps.clone();
+        steps.push(index.to_string());
+        Self {
+            json_path: format!("{}[{}]", self.json_path, index),
+            steps,
+            ..self.clone()
+        }
+    }
+}
+
+/// Every string in the resource attributes and outputs of the state document `root`,
+/// parsed from `text`
+pub fn strings<'a>(root: &'a JsonValue, text: &str) -> Vec<StateString<'a>> {
+    let mut strings = Vec::new();
+
+    let resources = root.get("resources").and_then(JsonValue::as_array).unwrap_or_default();
+    for (r, resource) in resources.iter().enumerate() {
+        let address = resource_address(resource);
+        let instances = resource.get("instances").and_then(JsonValue::as_array).unwrap_or_default();
+        for (i, instance) in instances.iter().enumerate() {
+            let Some(attributes) = instance.get("attributes") else {
+                continue;
+            };
+            let location = Location {
+                address: address.clone(),
+                json_path: format!("$.resources[{}].instances[{}].attributes", r, i),
+                key: String::new(),
+                steps: Vec::new(),
+            };
+            collect(attributes, &location, &sensitive_paths(instance, text), &mut strings);
+        }
+    }
+
+    for output in root.get("outputs").and_then(JsonValue::as_object).unwrap_or_default() {
+        let Some(value) = output.value.get("value") else {
+            continue;
+        };
+        let location = Location {
+            address: format!("output.{}", output.key),
+            json_path: format!("$.outputs.{}.value", output.key),
+            key: output.key.clone(),
+            steps: Vec::new(),
+        };
+        // An empty path marks the whole value
+        let sensitive = if output.value.get("sensitive").and_then(JsonValue::as_bool) == Some(true) {
+            vec![Vec::new()]
+        } else {
+            Vec::new()
+        };
+        collect(value, &location, &sensitive, &mut strings);
+    }
+
+    strings
+}
+
+/// `module.x.data.type.name` style address of a resource entry
+fn resource_address(resource: &JsonValue) -> String {
+    let field = |name| resource.get(name).and_then(JsonValue::as_str).unwrap_or_default();
+    let mut parts = Vec::new();
+    if !field("module").is_empty() {
+        parts.push(field("module"));
+    }
+    if field("mode") == "data" {
+        parts.push("data");
+    }
+    parts.push(field("type"));
+    parts.push(field("name"));
+    parts.join(".")
+}
+
+/// Attribute paths listed in an instance's `sensitive_attributes`, each a list of
+/// `{ "type": "get_attr" | "index", "value": ... }` steps. Paths with a step that
+/// cannot be read are dropped rather than shortened, which would widen them.
+fn sensitive_paths(instance: &JsonValue, text: &str) -> Vec<Vec<String>> {
+    let paths = instance
+        .get("sensitive_attributes")
+        .and_then(JsonValue::as_array)
+        .unwrap_or_default();
+    paths
+        .iter()
+        .filter_map(|path| {
+            let steps = path.as_array().unwrap_or(std::slice::from_ref(path));
+            steps
+                .iter()
+                .map(|step| {
+                    let value = step.get("value")?;
+                    // Index steps wrap their key as `{ "value": 0, "type": "number" }`
+                    let value = value.get("value").unwrap_or(value);
+                    match &value.kind {
+                        JsonKind::String(key) => Some(key.clone()),
+                        JsonKind::Number => text.get(value.span.clone()).map(str::to_string),
+                        _ => None,
+                    }
+                })
+                .collect::<Option<Vec<_>>>()
+        })
+        // An empty path would mark the whole instance
+        .filter(|path| !path.is_empty())
+        .collect()
+}
+
+fn collect<'a>(
+    value: &'a JsonValue,
+    location: &Location,
+    sensitive: &[Vec<String>],
+    strings: &mut Vec<StateString<'a>>,
+) {
+    match &value.kind {
+        JsonKind::String(text) => strings.push(StateString {
+            address: location.address.clone(),
+            json_path: location.json_path.clone(),
+            key: location.key.clone(),
+            value: text,
+            span: value.span.start + 1..value.span.end - 1,
+            sensitive: sensitive.iter().any(|path| location.steps.starts_with(path)),
+        }),
+        JsonKind::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                collect(item, &location.item(index), sensitive, strings);
+            }
+        }
+        JsonKind::Object(members) => {
+            for member in members {
+                collect(&member.value, &location.member(&member.key), sensitive, strings);
+            }
+        }
+        JsonKind::Null | JsonKind::Bool(_) | JsonKind::Number => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sensitive_attributes_mark_their_paths_only() {
+        let text = r#"{
+  "resources": [
+    {
+      "mode": "managed",
+      "type": "aws_db_instance",
+      "name": "main",
+      "instances": [
+        {
+          "attributes": { "username": "admin", "password": "hunter2", "tags": ["a", "b"] },
+          "sensitive_attributes": [
+            [{ "type": "get_attr", "value": "password" }],
+            [{ "type": "get_attr", "value": "tags" }, { "type": "index", "value": { "value": 1, "type": "number" } }],
+            [{ "type": "unknown" }],
+            [{ "type": "get_attr", "value": "username" }, { "type": "index", "value": null }]
+          ]
+        }
+      ]
+    }
+  ]
+}"#;
+        let root = crate::json::parse(text).unwrap();
+        let strings = strings(&root, text);
+        let summary: Vec<(&str, &str, bool)> = strings
+            .iter()
+            .map(|s| (s.address.as_str(), s.json_path.as_str(), s.sensitive))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("aws_db_instance.main.username", "$.resources[0].instances[0].attributes.username", false),
+                ("aws_db_instance.main.password", "$.resources[0].instances[0].attributes.password", true),
+                ("aws_db_instance.main.tags", "$.resources[0].instances[0].attributes.tags[0]", false),
+                ("aws_db_instance.main.tags", "$.resources[0].instances[0].attributes.tags[1]", true),
+            ]
+        );
+        assert_eq!(&text[strings[1].span.clone()], "hunter2");
+    }
+}
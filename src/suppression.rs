@@ -0,0 +1,434 @@
+//! Inline suppression comments.
+//!
+//! Supported grammar (with `#` or `//` comments):
+//!
+//! - `# forseti-ignore: <rule-id>[, ...] <reason>` on its own line suppresses the
+//!   next line, or the whole block when the next line opens one
+//! - the same comment at the end of a line suppresses that line only
+//! - `# forseti-ignore-file[: <rule-id>[, ...]]` suppresses the whole file
+//! - `# tflint-ignore: terraform_*` and `# tflint-ignore-file: ...` are honoured
+//!   for the tflint rules that have a Forseti equivalent
+//!
+//! Omitting the rule list (or using `all`) suppresses every rule.
+
+use forseti_sdk::core::Diagnostic;
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use hcl::edit::Span;
+use hcl::edit::expr::Expression;
+use hcl::edit::structure::Body;
+use hcl::edit::visit::{self, Visit};
+use std::ops::Range;
+use std::sync::Arc;
+
+use crate::cache::DocumentCache;
+use crate::options::TerraformRule;
+
+/// tflint rule names and the Forseti rules that cover the same check; a tflint
+/// rule may be split across several Forseti rules
+const TFLINT_EQUIVALENTS: &[(&str, &str)] = &[
+    ("terraform_naming_convention", "resource-naming-convention"),
+    ("terraform_documented_variables", "variable-description-required"),
+    ("terraform_documented_outputs", "output-description-required"),
+    ("terraform_required_providers", "require-provider-version"),
+    ("terraform_deprecated_interpolation", "no-deprecated-interpolation"),
+    ("terraform_deprecated_index", "no-legacy-syntax"),
+    ("terraform_unused_declarations", "no-unused-variables"),
+    ("terraform_unused_declarations", "no-unused-declarations"),
+    ("terraform_module_version", "require-module-version"),
+    ("terraform_module_pinned_source", "require-module-version"),
+    ("terraform_required_version", "require-terraform-version"),
+];
+
+/// Which rules a suppression applies to
+#[derive(Debug, Clone, PartialEq)]
+enum RuleSelector {
+    All,
+    Rules(Vec<String>),
+}
+
+impl RuleSelector {
+    fn matches(&self, rule_id: &str) -> bool {
+        match self {
+            RuleSelector::All => true,
+            RuleSelector::Rules(ids) => ids.iter().any(|id| id == rule_id),
+        }
+    }
+}
+
+/// A suppression covering an inclusive range of 0-based lines
+#[derive(Debug, Clone)]
+struct Suppression {
+    rules: RuleSelector,
+    first_line: u32,
+    last_line: u32,
+}
+
+/// A parsed `*-ignore` directive before its scope is resolved
+struct Directive {
+    rules: RuleSelector,
+    file_level: bool,
+}
+
+/// All suppressions declared in one file
+#[derive(Debug, Default)]
+pub struct Suppressions {
+    entries: Vec<Suppression>,
+}
+
+impl Suppressions {
+    /// Collect suppression comments from `text`, using `body` to scope block-level ones
+    pub fn parse(text: &str, body: Option<&Body>) -> Self {
+        let lines: Vec<&str> = text.lines().collect();
+        let block_lines = body.map(|b| Self::block_line_ranges(b, text)).unwrap_or_default();
+        let code_starts = Self::code_starts(text, body);
+        let mut entries = Vec::new();
+        let comment_in = |index: usize| {
+            let start = code_starts[index]?;
+            find_comment(&lines[index][start..]).map(|(offset, comment)| (start + offset, comment))
+        };
+        let is_comment_only = |index: usize| {
+            comment_in(index).is_some_and(|(start, _)| lines[index][..start].trim().is_empty())
+        };
+
+        for (index, line) in lines.iter().enumerate() {
+            let Some((comment_start, comment)) = comment_in(index) else {
+                continue;
+            };
+            let Some(directive) = parse_directive(comment) else {
+                continue;
+            };
+            let line_number = index as u32;
+
+            if directive.file_level {
+                entries.push(Suppression {
+                    rules: directive.rules,
+                    first_line: 0,
+                    last_line: u32::MAX,
+                });
+            } else if line[..comment_start].trim().is_empty() {
+                // Own-line comment: applies to the next line that is not itself a comment
+                let Some(target) = (index + 1..lines.len()).find(|&i| !is_comment_only(i))
+                else {
+                    continue;
+                };
+                let target = target as u32;
+                let last_line = block_lines
+                    .iter()
+                    .filter(|(start, _)| *start == target)
+                    .map(|(_, end)| *end)
+                    .max()
+                    .unwrap_or(target);
+                entries.push(Suppression {
+                    rules: directive.rules,
+                    first_line: target,
+                    last_line,
+                });
+            } else {
+                // Trailing comment: applies to its own line
+                entries.push(Suppression {
+                    rules: directive.rules,
+                    first_line: line_number,
+                    last_line: line_number,
+                });
+            }
+        }
+
+        Self { entries }
+    }
+
+    /// Whether a diagnostic is covered by a suppression comment
+    pub fn suppresses(&self, diagnostic: &Diagnostic) -> bool {
+        let line = diagnostic.range.start.line;
+        self.entries.iter().any(|s| {
+            s.first_line <= line && line <= s.last_line && s.rules.matches(&diagnostic.rule_id)
+        })
+    }
+
+    /// For each line, the byte offset where code starts: past the end of a multi-line
+    /// string (heredoc or template) the line begins in, or None when the whole line is
+    /// string content, whose `#` and `//` are not comments
+    fn code_starts(text: &str, body: Option<&Body>) -> Vec<Option<usize>> {
+        let mut strings = MultilineStrings {
+            text,
+            spans: Vec::new(),
+        };
+        if let Some(body) = body {
+            strings.visit_body(body);
+        }
+
+        let mut line_start = 0;
+        text.lines()
+            .map(|line| {
+                let start = line_start;
+                line_start += line.len();
+                line_start += text[line_start..].find('\n').map_or(0, |newline| newline + 1);
+                match strings.spans.iter().find(|span| span.start < start && start <= span.end) {
+                    Some(span) if span.end < start + line.len() => Some(span.end - start),
+                    Some(_) => None,
+                    None => Some(0),
+                }
+            })
+            .collect()
+    }
+
+    /// Start and end lines of every block, nested ones included
+    fn block_line_ranges(body: &Body, text: &str) -> Vec<(u32, u32)> {
+        let mut ranges = Vec::new();
+        for block in body.blocks() {
+            if let Some(span) = block.span() {
+                ranges.push((line_of(text, span.start), line_of(text, span.end)));
+            }
+            ranges.extend(Self::block_line_ranges(&block.body, text));
+        }
+        ranges
+    }
+}
+
+/// 0-based line of a byte offset
+fn line_of(text: &str, offset: usize) -> u32 {
+    text[..offset.min(text.len())].matches('\n').count() as u32
+}
+
+/// Spans of the string expressions that run over several lines
+struct MultilineStrings<'a> {
+    text: &'a str,
+    spans: Vec<Range<usize>>,
+}
+
+impl Visit for MultilineStrings<'_> {
+    fn visit_expr(&mut self, expr: &Expression) {
+        if matches!(
+            expr,
+            Expression::String(_) | Expression::StringTemplate(_) | Expression::HeredocTemplate(_)
+        ) && let Some(span) = expr.span()
+            && self.text.get(span.clone()).is_some_and(|source| source.contains('\n'))
+        {
+            self.spans.push(span);
+            return;
+        }
+        visit::visit_expr(self, expr);
+    }
+}
+
+/// Find a `#` or `//` comment outside string literals, returning its offset and text
+fn find_comment(line: &str) -> Option<(usize, &str)> {
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        if in_string {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match ch {
+            '"' => in_string = true,
+            '#' => return Some((i, &line[i + 1..])),
+            '/' if chars.peek().is_some_and(|(_, next)| *next == '/') => {
+                return Some((i, &line[i + 2..]));
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Parse the body of a comment as a forseti or tflint ignore directive
+fn parse_directive(comment: &str) -> Option<Directive> {
+    let comment = comment.trim();
+    let (rest, tflint) = if let Some(rest) = comment.strip_prefix("forseti-ignore") {
+        (rest, false)
+    } else if let Some(rest) = comment.strip_prefix("tflint-ignore") {
+        (rest, true)
+    } else {
+        return None;
+    };
+
+    let (rest, file_level) = match rest.strip_prefix("-file") {
+        Some(rest) => (rest, true),
+        None => (rest, false),
+    };
+    // Reject longer words such as `forseti-ignored`
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '-' || c == '_') {
+        return None;
+    }
+
+    let ids = parse_rule_list(rest.trim_start().trim_start_matches(':'));
+    let rules = if ids.is_empty() || ids.iter().any(|id| id == "all") {
+        RuleSelector::All
+    } else if tflint {
+        let mapped: Vec<String> = ids
+            .iter()
+            .flat_map(|id| {
+                TFLINT_EQUIVALENTS
+                    .iter()
+                    .filter(move |(tflint_id, _)| tflint_id == id)
+                    .map(|(_, forseti_id)| forseti_id.to_string())
+            })
+            .collect();
+        if mapped.is_empty() {
+            return None; // Only tflint rules without a Forseti equivalent
+        }
+        RuleSelector::Rules(mapped)
+    } else {
+        RuleSelector::Rules(ids)
+    };
+
+    Some(Directive { rules, file_level })
+}
+
+/// Read a comma-separated rule list, stopping at the free-form reason that follows
+fn parse_rule_list(text: &str) -> Vec<String> {
+    let mut ids = Vec::new();
+    let mut expect_more = true;
+
+    for word in text.split_whitespace() {
+        if !expect_more && !word.starts_with(',') {
+            break; // Start of the reason
+        }
+        ids.extend(
+            word.split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string),
+        );
+        expect_more = word.ends_with(',');
+    }
+
+    ids
+}
+
+/// Wraps a rule so its diagnostics honour the file's suppression comments
+pub struct SuppressibleRule {
+    rule: Box<dyn TerraformRule>,
+    documents: Arc<DocumentCache>,
+}
+
+impl SuppressibleRule {
+    pub fn new(rule: Box<dyn TerraformRule>, documents: Arc<DocumentCache>) -> Self {
+        Self { rule, documents }
+    }
+}
+
+impl Rule for SuppressibleRule {
+    fn id(&self) -> &'static str {
+        self.rule.id()
+    }
+
+    fn description(&self) -> &'static str {
+        self.rule.description()
+    }
+
+    fn default_config(&self) -> serde_json::Value {
+        self.rule.default_config()
+    }
+
+    fn check(&self, ctx: &mut RuleContext) {
+        let mut inner = RuleContext {
+            uri: ctx.uri,
+            text: ctx.text,
+            options: ctx.options,
+            diagnostics: vec![],
+            annotations: ctx.annotations,
+            annotation_parser: ctx.annotation_parser,
+        };
+        self.rule.check(&mut inner);
+
+        if inner.diagnostics.is_empty() {
+            return;
+        }
+        let document = self.documents.get_or_parse(ctx.uri, ctx.text);
+        for diagnostic in inner.diagnostics {
+            if !document.suppressions.suppresses(&diagnostic) {
+                ctx.report(diagnostic);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostic::DiagnosticBuilder;
+    use crate::utils::TerraformUtils;
+
+    fn parse(text: &str) -> Suppressions {
+        let body = TerraformUtils::parse_hcl(text).expect("valid HCL");
+        Suppressions::parse(text, Some(&body))
+    }
+
+    /// Whether a diagnostic of `rule_id` on the 0-based `line` is suppressed
+    fn suppressed(suppressions: &Suppressions, rule_id: &str, line: u32) -> bool {
+        let position = forseti_sdk::core::Position { line, character: 0 };
+        let diagnostic = DiagnosticBuilder::new(rule_id, "")
+            .with_range(forseti_sdk::core::Range {
+                start: position,
+                end: position,
+            })
+            .build("error");
+        suppressions.suppresses(&diagnostic)
+    }
+
+    #[test]
+    fn own_line_directive_covers_the_next_line() {
+        let suppressions = parse(
+            "locals {\n  # forseti-ignore: rule-a because\n  # another comment\n  a = 1\n  b = 2\n}\n",
+        );
+        assert!(suppressed(&suppressions, "rule-a", 3));
+        assert!(!suppressed(&suppressions, "rule-b", 3));
+        assert!(!suppressed(&suppressions, "rule-a", 4));
+    }
+
+    #[test]
+    fn trailing_directive_covers_its_line() {
+        let suppressions = parse("locals {\n  a = \"#\" // forseti-ignore: rule-a, rule-b\n  b = 2\n}\n");
+        assert!(suppressed(&suppressions, "rule-a", 1));
+        assert!(suppressed(&suppressions, "rule-b", 1));
+        assert!(!suppressed(&suppressions, "rule-a", 2));
+    }
+
+    #[test]
+    fn directive_before_a_block_covers_the_block() {
+        let suppressions = parse("# forseti-ignore\nresource \"a\" \"b\" {\n  c = 1\n}\nlocals {}\n");
+        assert!(suppressed(&suppressions, "rule-a", 2));
+        assert!(suppressed(&suppressions, "rule-b", 3));
+        assert!(!suppressed(&suppressions, "rule-a", 4));
+    }
+
+    #[test]
+    fn tflint_directives_map_to_equivalent_rules() {
+        let suppressions = parse(
+            "# tflint-ignore: terraform_unused_declarations\nvariable \"a\" {}\n# tflint-ignore: aws_instance_invalid_type\nvariable \"b\" {}\n",
+        );
+        assert!(suppressed(&suppressions, "no-unused-variables", 1));
+        assert!(suppressed(&suppressions, "no-unused-declarations", 1));
+        assert!(!suppressed(&suppressions, "no-legacy-syntax", 1));
+        assert!(!suppressed(&suppressions, "no-unused-variables", 3));
+
+        let suppressions = parse("# tflint-ignore-file: terraform_deprecated_index\nlocals {}\n");
+        assert!(suppressed(&suppressions, "no-legacy-syntax", 1));
+        assert!(!suppressed(&suppressions, "rule-a", 1));
+    }
+
+    #[test]
+    fn comments_inside_strings_are_not_directives() {
+        let suppressions = parse(
+            "locals {\n  a = <<EOT\n# forseti-ignore-file\n  // forseti-ignore: rule-a\nEOT\n  b = \"${join(\",\",\n  [\"# forseti-ignore-file\"])}\"\n  c = 1\n}\n",
+        );
+        for line in 0..8 {
+            assert!(!suppressed(&suppressions, "rule-a", line), "line {}", line);
+        }
+    }
+
+    #[test]
+    fn directive_after_a_multiline_string_counts() {
+        let suppressions = parse("locals {\n  a = <<EOT\ntext\nEOT\n  # forseti-ignore: rule-a\n  b = 1\n}\n");
+        assert!(suppressed(&suppressions, "rule-a", 5));
+        assert!(!suppressed(&suppressions, "rule-a", 2));
+    }
+}
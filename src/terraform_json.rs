@@ -0,0 +1,503 @@
+//! Terraform JSON syntax (`*.tf.json`, `*.tfvars.json`) mapped onto the HCL AST.
+//!
+//! hcl-edit only records spans for nodes it parses itself, so each block is
+//! rewritten as a "shadow" HCL text in which every token sits at the byte offset
+//! of the JSON token it came from, then parsed. Spans in the resulting AST
+//! therefore index straight into the JSON document and every rule works on it
+//! unchanged. Blocks are shadowed one at a time because JSON shares the block
+//! type and type label between sibling blocks, while HCL repeats them.
+//!
+//! Every HCL token is exactly as long as its JSON token, or shorter, so offsets
+//! never drift. The few strings HCL cannot spell in the same number of bytes get
+//! a placeholder of the right length, and their value is restored after parsing.
+
+use hcl::edit::Span;
+use hcl::edit::repr::{Decorated, Spanned};
+use hcl::edit::structure::{Block, Body, Structure};
+use hcl::edit::visit_mut::VisitMut;
+use std::ops::Range;
+
+use crate::cache::SyntaxError;
+use crate::json::{self, JsonKind, JsonMember, JsonValue};
+use crate::utils::TerraformUtils;
+
+/// Parse a Terraform JSON document; `variables_file` selects `.tfvars.json` semantics
+pub fn parse_body(text: &str, variables_file: bool) -> Result<Body, SyntaxError> {
+    let root = json::parse(text)?;
+    let Some(members) = root.as_object() else {
+        return Err(SyntaxError {
+            message: "expected a JSON object at the top level".to_string(),
+            offset: root.span.start,
+        });
+    };
+
+    if variables_file {
+        // Every property is a variable assignment with a literal value
+        let mut shadow = Shadow::new(text);
+        for member in members {
+            shadow.attribute(member, ValueMode::Literal);
+        }
+        return shadow.parse_body();
+    }
+
+    let mut body = Body::new();
+    for member in members {
+        let Some(label_count) = block_labels(None, &member.key) else {
+            continue;
+        };
+        for instance in BlockInstance::expand(member, label_count) {
+            body.push(instance.parse(text)?);
+        }
+    }
+    Ok(body)
+}
+
+/// Number of labels of a block type nested in `parent` (None at the top level),
+/// or None if `key` is not a block there
+fn block_labels(parent: Option<&str>, key: &str) -> Option<usize> {
+    match (parent, key) {
+        (None, "resource" | "data" | "ephemeral") => Some(2),
+        (None, "variable" | "output" | "module" | "provider" | "check") => Some(1),
+        (None, "locals" | "terraform" | "moved" | "import" | "removed") => Some(0),
+        (Some("resource" | "data" | "ephemeral"), "lifecycle" | "connection") => Some(0),
+        (Some("resource" | "data" | "ephemeral" | "content"), "provisioner" | "dynamic") => Some(1),
+        (Some("provisioner"), "connection") => Some(0),
+        (Some("dynamic"), "content") => Some(0),
+        (Some("lifecycle"), "precondition" | "postcondition") => Some(0),
+        (Some("variable"), "validation") => Some(0),
+        (Some("output"), "precondition") => Some(0),
+        (Some("terraform"), "required_providers" | "cloud") => Some(0),
+        (Some("terraform"), "backend") => Some(1),
+        (Some("cloud"), "workspaces") => Some(0),
+        (Some("check"), "data") => Some(2),
+        (Some("check"), "assert") => Some(0),
+        _ => None,
+    }
+}
+
+/// How string values are interpreted
+#[derive(Clone, Copy, PartialEq)]
+enum ValueMode {
+    /// Strings are templates, as in most configuration arguments
+    Template,
+    /// Strings hold an expression, as in `depends_on` or a variable's `type`
+    Expression,
+    /// Strings are plain values, as in tfvars files
+    Literal,
+}
+
+/// Arguments whose JSON strings are parsed as expressions rather than templates
+fn value_mode(block_type: &str, attribute: &str) -> ValueMode {
+    match (block_type, attribute) {
+        (_, "depends_on" | "provider" | "providers" | "ignore_changes") => ValueMode::Expression,
+        ("variable", "type") => ValueMode::Expression,
+        ("moved" | "removed", "from") | ("moved" | "import", "to") => ValueMode::Expression,
+        _ => ValueMode::Template,
+    }
+}
+
+/// One block of the document: JSON nests labels as objects and allows arrays of
+/// bodies, so a single property can expand to many blocks
+struct BlockInstance<'a> {
+    ident: &'a JsonMember,
+    labels: Vec<&'a JsonMember>,
+    body: &'a JsonValue,
+}
+
+impl<'a> BlockInstance<'a> {
+    fn expand(member: &'a JsonMember, label_count: usize) -> Vec<Self> {
+        let mut instances = Vec::new();
+        Self::expand_into(member, &member.value, Vec::new(), label_count, &mut instances);
+        instances
+    }
+
+    fn expand_into(
+        ident: &'a JsonMember,
+        value: &'a JsonValue,
+        labels: Vec<&'a JsonMember>,
+        label_count: usize,
+        instances: &mut Vec<Self>,
+    ) {
+        match &value.kind {
+            JsonKind::Array(items) => {
+                for item in items {
+                    Self::expand_into(ident, item, labels.clone(), label_count, instances);
+                }
+            }
+            JsonKind::Object(_) if labels.len() == label_count => instances.push(Self {
+                ident,
+                labels,
+                body: value,
+            }),
+            JsonKind::Object(members) => {
+                for member in members.iter().filter(|m| !is_comment(m)) {
+                    let mut labels = labels.clone();
+                    labels.push(member);
+                    Self::expand_into(ident, &member.value, labels, label_count, instances);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Shadow and parse this block, then attach its nested blocks.
+    ///
+    /// The header (type, labels and braces) and the body are parsed separately: a
+    /// multi-line HCL body needs a newline before its closing brace, which compact
+    /// JSON has no room for.
+    fn parse(&self, text: &str) -> Result<Block, SyntaxError> {
+        let block_type = self.ident.key.as_str();
+        let members = self.body.as_object().unwrap_or_default();
+
+        let mut header = Shadow::new(text);
+        header.at(self.ident.key_span.start + 1, block_type);
+        for label in &self.labels {
+            header.string(&label.key_span, ValueMode::Template);
+        }
+        header.at(self.body.span.start, "{");
+        header.at(self.body.span.end - 1, "}");
+
+        let mut shadow = Shadow::new(text);
+        let mut nested = Vec::new();
+        for member in members.iter().filter(|m| !is_comment(m)) {
+            match block_labels(Some(block_type), &member.key) {
+                Some(label_count) => nested.extend(BlockInstance::expand(member, label_count)),
+                None => {
+                    shadow.attribute(member, value_mode(block_type, &member.key));
+                }
+            }
+        }
+
+        let Some(Structure::Block(mut block)) = header.parse_body()?.into_iter().next() else {
+            return Err(SyntaxError {
+                message: format!("could not map '{}' block", block_type),
+                offset: self.ident.key_span.start,
+            });
+        };
+        block.body = shadow.parse_body()?;
+        for instance in nested {
+            block.body.push(instance.parse(text)?);
+        }
+        Ok(block)
+    }
+}
+
+/// `"//"` properties are comments in Terraform JSON
+fn is_comment(member: &JsonMember) -> bool {
+    member.key == "//"
+}
+
+/// HCL text whose tokens are written at the offsets of the JSON tokens they stand for
+struct Shadow<'a> {
+    json: &'a str,
+    text: String,
+    /// Spans of string tokens written with a placeholder instead of their value
+    placeholders: Vec<Range<usize>>,
+}
+
+impl<'a> Shadow<'a> {
+    fn new(json: &'a str) -> Self {
+        Self {
+            json,
+            text: String::new(),
+            placeholders: Vec::new(),
+        }
+    }
+
+    /// Write `token` at byte `offset`, padding with blanks. Tokens are never longer
+    /// than the JSON they replace, so earlier output never reaches past `offset`.
+    fn at(&mut self, offset: usize, token: &str) {
+        let len = self.text.len();
+        if len < offset {
+            if len == 0 && offset >= 4 {
+                // A single comment skips the leading padding faster than blanks
+                self.text.push_str("/*");
+                self.text.extend(std::iter::repeat_n(' ', offset - 4));
+                self.text.push_str("*/");
+            } else {
+                self.text.extend(std::iter::repeat_n(' ', offset - len));
+            }
+        }
+        self.text.push_str(token);
+    }
+
+    /// Write `key = value`, returning false for keys HCL cannot express as identifiers
+    fn attribute(&mut self, member: &JsonMember, mode: ValueMode) -> bool {
+        if is_comment(member) || !TerraformUtils::is_identifier(&member.key) {
+            return false;
+        }
+        self.at(member.key_span.start, "\n");
+        self.at(member.key_span.start + 1, &member.key);
+        self.at(member.key_span.end, "=");
+        self.value(&member.value, mode);
+        true
+    }
+
+    /// Write `separator` right after every item but the last one, where JSON has its commas
+    fn separate(&mut self, index: usize, count: usize, end: usize, separator: &str) {
+        if index + 1 < count {
+            self.at(end, separator);
+        }
+    }
+
+    fn value(&mut self, value: &JsonValue, mode: ValueMode) {
+        let span = &value.span;
+        match &value.kind {
+            JsonKind::Null | JsonKind::Bool(_) | JsonKind::Number => {
+                self.at(span.start, &self.json[span.clone()]);
+            }
+            JsonKind::String(_) => self.string(span, mode),
+            JsonKind::Array(items) => {
+                self.at(span.start, "[");
+                for (index, item) in items.iter().enumerate() {
+                    self.value(item, mode);
+                    self.separate(index, items.len(), item.span.end, ",");
+                }
+                self.at(span.end - 1, "]");
+            }
+            JsonKind::Object(members) => {
+                self.at(span.start, "{");
+                for (index, member) in members.iter().enumerate() {
+                    self.string(&member.key_span, ValueMode::Template);
+                    self.at(member.key_span.end, "=");
+                    self.value(&member.value, mode);
+                    self.separate(index, members.len(), member.value.span.end, ",");
+                }
+                self.at(span.end - 1, "}");
+            }
+        }
+    }
+
+    /// Write a JSON string token as HCL
+    fn string(&mut self, span: &Range<usize>, mode: ValueMode) {
+        let raw = &self.json[span.clone()];
+        let inner = &raw[1..raw.len() - 1];
+
+        if mode == ValueMode::Expression && !inner.is_empty() && !inner.contains(['\\', '$', '%']) {
+            // The quotes become blanks around the bare expression
+            self.at(span.start + 1, inner);
+            return;
+        }
+
+        let (converted, exact) = Self::convert(inner, mode);
+        if !exact {
+            self.placeholders.push(span.clone());
+        }
+        self.at(span.start, &format!("\"{}\"", converted));
+    }
+
+    /// Spell a JSON string's content in HCL with the same length. HCL shares JSON's
+    /// escapes except for surrogate pairs, and literal strings must not start
+    /// template sequences; both are replaced by placeholders, and the result says
+    /// whether the value came out exact.
+    fn convert(inner: &str, mode: ValueMode) -> (String, bool) {
+        let mut converted = String::with_capacity(inner.len());
+        let mut exact = true;
+        let mut rest = inner;
+        while let Some(ch) = rest.chars().next() {
+            let surrogate = rest
+                .strip_prefix("\\u")
+                .and_then(|hex| hex.get(..4))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .is_some_and(|code| (0xD800..0xE000).contains(&code));
+            let (token, placeholder) = if surrogate {
+                (6, "\\uFFFD")
+            } else if ch == '\\' {
+                // Two-character escapes and \uXXXX are copied as they are
+                let length = 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+                (length, &rest[..length])
+            } else if mode == ValueMode::Literal && (rest.starts_with("${") || rest.starts_with("%{")) {
+                (2, "__")
+            } else {
+                (ch.len_utf8(), &rest[..ch.len_utf8()])
+            };
+            exact &= placeholder == &rest[..token];
+            converted.push_str(placeholder);
+            rest = &rest[token..];
+        }
+        (converted, exact)
+    }
+
+    /// Parse the shadow text, then put the real values back into placeholder strings
+    fn parse_body(self) -> Result<Body, SyntaxError> {
+        let mut body = TerraformUtils::parse_hcl(&self.text).map_err(SyntaxError::from)?;
+        if !self.placeholders.is_empty() {
+            let mut restore = Restore {
+                json: self.json,
+                placeholders: &self.placeholders,
+            };
+            restore.visit_body_mut(&mut body);
+        }
+        Ok(body)
+    }
+}
+
+/// Puts the decoded JSON value into strings that were shadowed with placeholders.
+/// Object keys cannot be changed in place and keep the placeholder.
+struct Restore<'a> {
+    json: &'a str,
+    placeholders: &'a [Range<usize>],
+}
+
+impl Restore<'_> {
+    fn placeholder(&self, span: &Range<usize>) -> bool {
+        self.placeholders
+            .iter()
+            .any(|placeholder| placeholder.start <= span.start && span.end <= placeholder.end)
+    }
+
+    /// Decode the JSON string content at `span` (without quotes)
+    fn decode(&self, span: Range<usize>) -> Option<String> {
+        let quoted = format!("\"{}\"", self.json.get(span)?);
+        json::parse(&quoted).ok()?.as_str().map(str::to_string)
+    }
+}
+
+impl VisitMut for Restore<'_> {
+    fn visit_string_mut(&mut self, node: &mut Decorated<String>) {
+        if let Some(span) = node.span()
+            && self.placeholder(&span)
+            && let Some(value) = self.decode(span.start + 1..span.end - 1)
+        {
+            *node.value_mut() = value;
+        }
+    }
+
+    fn visit_literal_mut(&mut self, node: &mut Spanned<String>) {
+        // Template literals: undo HCL's own escapes of template sequences as well
+        if let Some(span) = node.span()
+            && self.placeholder(&span)
+            && let Some(value) = self.decode(span)
+        {
+            *node.value_mut() = value.replace("$${", "${").replace("%%{", "%{");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hcl::edit::expr::Expression;
+
+    /// Source text under the span of `node`
+    fn source<'a>(text: &'a str, node: &impl Span) -> &'a str {
+        &text[node.span().unwrap()]
+    }
+
+    fn blocks(body: &Body) -> Vec<&Block> {
+        body.blocks().collect()
+    }
+
+    #[test]
+    fn compact_spans_are_exact() {
+        let text = r#"{"resource":{"aws_instance":{"web":{"ami":"ami-1","tags":{"a":"x","b":["y","z"]},"count":2}}}}"#;
+        let body = parse_body(text, false).unwrap();
+        let block = blocks(&body)[0];
+        assert_eq!(block.ident.as_str(), "resource");
+        assert_eq!(source(text, block), &text[2..text.len() - 3]);
+
+        let ami = block.body.get_attribute("ami").unwrap();
+        assert_eq!(source(text, &ami.value), r#""ami-1""#);
+        assert_eq!(source(text, &ami.key), "ami");
+        let tags = block.body.get_attribute("tags").unwrap();
+        assert_eq!(source(text, &tags.value), r#"{"a":"x","b":["y","z"]}"#);
+        let Expression::Object(object) = &tags.value else {
+            panic!("tags is not an object");
+        };
+        let (_, b) = object.iter().nth(1).unwrap();
+        assert_eq!(source(text, b.expr()), r#"["y","z"]"#);
+        let count = block.body.get_attribute("count").unwrap();
+        assert_eq!(source(text, &count.value), "2");
+    }
+
+    #[test]
+    fn labels_nest_and_arrays_repeat_blocks() {
+        let text = r#"{
+  "resource": {
+    "aws_instance": {
+      "a": [{"ami": "one"}, {"ami": "two"}],
+      "b": {"ami": "three", "lifecycle": {"create_before_destroy": true}}
+    }
+  },
+  "variable": [{"x": {}}, {"y": {"default": 1}}]
+}"#;
+        let body = parse_body(text, false).unwrap();
+        let labels: Vec<Vec<&str>> = blocks(&body)
+            .iter()
+            .map(|block| {
+                let mut labels = vec![block.ident.as_str()];
+                labels.extend(block.labels.iter().map(|label| label.as_str()));
+                labels
+            })
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                vec!["resource", "aws_instance", "a"],
+                vec!["resource", "aws_instance", "a"],
+                vec!["resource", "aws_instance", "b"],
+                vec!["variable", "x"],
+                vec!["variable", "y"],
+            ]
+        );
+        let second = blocks(&body)[1].body.get_attribute("ami").unwrap();
+        assert_eq!(source(text, &second.value), r#""two""#);
+        let lifecycle = blocks(&body)[2].body.get_blocks("lifecycle").next().unwrap();
+        assert_eq!(source(text, lifecycle), r#"lifecycle": {"create_before_destroy": true}"#);
+        assert!(lifecycle.body.get_attribute("create_before_destroy").is_some());
+    }
+
+    #[test]
+    fn comment_keys_are_skipped() {
+        let text = r#"{"//": "top", "locals": {"//": "note", "a": 1}, "output": {"//": {"x": {}}, "o": {"value": 2}}}"#;
+        let body = parse_body(text, false).unwrap();
+        let found = blocks(&body);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].body.attributes().count(), 1);
+        assert_eq!(found[1].labels[0].as_str(), "o");
+    }
+
+    #[test]
+    fn escapes_keep_their_value_and_place() {
+        let text = r#"{"locals": {"a": "x\/y\bé", "b": "\uD83D\uDE00", "c": "${var.x}"}}"#;
+        let body = parse_body(text, false).unwrap();
+        let locals = &blocks(&body)[0].body;
+        let a = locals.get_attribute("a").unwrap();
+        assert_eq!(a.value.as_str(), Some("x/y\u{8}é"));
+        assert_eq!(source(text, &a.value), r#""x\/y\bé""#);
+        let b = locals.get_attribute("b").unwrap();
+        assert_eq!(b.value.as_str(), Some("😀"));
+        assert_eq!(source(text, &b.value), r#""\uD83D\uDE00""#);
+        let c = locals.get_attribute("c").unwrap();
+        assert!(matches!(c.value, Expression::StringTemplate(_)));
+        assert_eq!(source(text, &c.value), r#""${var.x}""#);
+    }
+
+    #[test]
+    fn variables_files_hold_literal_values() {
+        let text = r#"{"a":"${not_a_template}","b":["\uD83D\uDE00"],"c":{"k":"%{x}"}}"#;
+        let body = parse_body(text, true).unwrap();
+        let a = body.get_attribute("a").unwrap();
+        assert_eq!(a.value.as_str(), Some("${not_a_template}"));
+        assert_eq!(source(text, &a.value), r#""${not_a_template}""#);
+        let Expression::Array(b) = &body.get_attribute("b").unwrap().value else {
+            panic!("b is not an array");
+        };
+        assert_eq!(b.iter().next().unwrap().as_str(), Some("😀"));
+        let Expression::Object(c) = &body.get_attribute("c").unwrap().value else {
+            panic!("c is not an object");
+        };
+        assert_eq!(c.iter().next().unwrap().1.expr().as_str(), Some("%{x}"));
+    }
+
+    #[test]
+    fn expression_strings_are_parsed() {
+        let text = r#"{"variable":{"v":{"type":"list(string)"}},"resource":{"a":{"b":{"depends_on":["aws_s3_bucket.x"]}}}}"#;
+        let body = parse_body(text, false).unwrap();
+        let variable = blocks(&body)[0].body.get_attribute("type").unwrap();
+        assert!(matches!(variable.value, Expression::FuncCall(_)));
+        assert_eq!(source(text, &variable.value), "list(string)");
+        let depends_on = blocks(&body)[1].body.get_attribute("depends_on").unwrap();
+        assert_eq!(source(text, &depends_on.value), r#"["aws_s3_bucket.x"]"#);
+    }
+}
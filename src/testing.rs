@@ -0,0 +1,60 @@
+//! Helpers for running rules in unit tests
+
+use forseti_sdk::core::{Diagnostic, Position};
+use forseti_sdk::ruleset::{Rule, RuleContext};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Held by tests that start a new run, or that rely on what has been reported so far
+/// in the current one
+pub static RUN: Mutex<()> = Mutex::new(());
+
+/// Run `rule` on `text` as the file `uri` with the rule config `options`
+pub fn run(rule: &dyn Rule, uri: &str, text: &str, options: Value) -> Vec<Diagnostic> {
+    let mut ctx = RuleContext {
+        uri,
+        text,
+        options: &options,
+        diagnostics: Vec::new(),
+        annotations: &[],
+        annotation_parser: None,
+    };
+    rule.check(&mut ctx);
+    ctx.diagnostics
+}
+
+/// Diagnostic codes in report order
+pub fn codes(diagnostics: &[Diagnostic]) -> Vec<&str> {
+    diagnostics.iter().filter_map(|d| d.code.as_deref()).collect()
+}
+
+/// `text` with the first suggested fix of `diagnostic` applied
+pub fn apply_fix(text: &str, diagnostic: &Diagnostic) -> Option<String> {
+    let fix = diagnostic.suggest.as_ref()?.first()?.fix.as_ref()?;
+    let start = offset(text, &fix.range.start);
+    let end = offset(text, &fix.range.end);
+    Some(format!("{}{}{}", &text[..start], fix.text, &text[end..]))
+}
+
+/// Byte offset of an LSP position counted in characters
+fn offset(text: &str, position: &Position) -> usize {
+    let mut line = 0;
+    let mut character = 0;
+    for (index, ch) in text.char_indices() {
+        if line == position.line && character == position.character {
+            return index;
+        }
+        if ch == '\n' {
+            line += 1;
+            character = 0;
+        } else {
+            character += 1;
+        }
+    }
+    text.len()
+}
+
+/// The part of `text` covered by the range of `diagnostic`
+pub fn covered<'a>(text: &'a str, diagnostic: &Diagnostic) -> &'a str {
+    &text[offset(text, &diagnostic.range.start)..offset(text, &diagnostic.range.end)]
+}
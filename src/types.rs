@@ -0,0 +1,238 @@
+use hcl::edit::expr::{Expression, ObjectKey};
+use hcl::edit::template::Element;
+use std::fmt;
+
+use crate::utils::TerraformUtils;
+
+/// A Terraform type constraint as written in the `type` argument of a `variable` block
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeConstraint {
+    Any,
+    String,
+    Number,
+    Bool,
+    List(Box<TypeConstraint>),
+    Set(Box<TypeConstraint>),
+    Map(Box<TypeConstraint>),
+    Tuple(Vec<TypeConstraint>),
+    Object(Vec<ObjectAttribute>),
+}
+
+/// One attribute of an `object({...})` type
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjectAttribute {
+    pub name: String,
+    pub constraint: TypeConstraint,
+    /// Declared with `optional(...)`
+    pub optional: bool,
+}
+
+impl TypeConstraint {
+    /// Parse a type expression such as `list(object({ name = string }))`.
+    ///
+    /// The quoted forms of Terraform 0.11 (`"string"`, `"list"`, `"map"`) are accepted too.
+    /// Returns None for expressions that are not valid type constraints.
+    pub fn parse(expr: &Expression) -> Option<Self> {
+        match expr {
+            Expression::Variable(ident) => match ident.as_str() {
+                "any" => Some(Self::Any),
+                "string" => Some(Self::String),
+                "number" => Some(Self::Number),
+                "bool" => Some(Self::Bool),
+                "list" => Some(Self::List(Box::new(Self::Any))),
+                "set" => Some(Self::Set(Box::new(Self::Any))),
+                "map" => Some(Self::Map(Box::new(Self::Any))),
+                _ => None,
+            },
+            Expression::String(value) => match value.as_str() {
+                "string" => Some(Self::String),
+                "list" => Some(Self::List(Box::new(Self::Any))),
+                "map" => Some(Self::Map(Box::new(Self::Any))),
+                _ => None,
+            },
+            Expression::FuncCall(call) if call.name.namespace.is_empty() => {
+                let args: Vec<&Expression> = call.args.iter().collect();
+                let element = || Some(Box::new(Self::parse(args.first()?)?));
+                match (call.name.name.as_str(), args.len()) {
+                    ("list", 1) => Some(Self::List(element()?)),
+                    ("set", 1) => Some(Self::Set(element()?)),
+                    ("map", 1) => Some(Self::Map(element()?)),
+                    ("tuple", 1) => match args[0] {
+                        Expression::Array(items) => {
+                            items.iter().map(Self::parse).collect::<Option<_>>().map(Self::Tuple)
+                        }
+                        _ => None,
+                    },
+                    ("object", 1) => match args[0] {
+                        Expression::Object(object) => object
+                            .iter()
+                            .map(|(key, value)| ObjectAttribute::parse(key, value.expr()))
+                            .collect::<Option<_>>()
+                            .map(Self::Object),
+                        _ => None,
+                    },
+                    _ => None,
+                }
+            }
+            Expression::Parenthesis(inner) => Self::parse(inner.inner()),
+            _ => None,
+        }
+    }
+
+    /// Check that a literal value converts to this type, describing the first
+    /// mismatch when it does not
+    pub fn check(&self, value: &Expression) -> Result<(), String> {
+        self.check_at(value, "")
+    }
+
+    fn check_at(&self, value: &Expression, path: &str) -> Result<(), String> {
+        let mismatch = || Err(format!("expected {}, got {}{}", self, describe(value), location(path)));
+
+        match (self, value) {
+            (_, Expression::Null(_)) | (Self::Any, _) => Ok(()),
+            (_, Expression::Parenthesis(inner)) => self.check_at(inner.inner(), path),
+            (Self::String, Expression::String(_) | Expression::Number(_) | Expression::Bool(_)) => Ok(()),
+            (Self::String, value) if is_literal_string(value) => Ok(()),
+            (Self::Number, Expression::Number(_)) => Ok(()),
+            (Self::Number, Expression::UnaryOp(op)) if matches!(op.expr, Expression::Number(_)) => Ok(()),
+            (Self::Number, Expression::String(s)) if s.trim().parse::<f64>().is_ok() => Ok(()),
+            (Self::Bool, Expression::Bool(_)) => Ok(()),
+            (Self::Bool, Expression::String(s)) if matches!(s.as_str(), "true" | "false") => Ok(()),
+            (Self::List(element) | Self::Set(element), Expression::Array(items)) => {
+                for (i, item) in items.iter().enumerate() {
+                    element.check_at(item, &format!("{}[{}]", path, i))?;
+                }
+                Ok(())
+            }
+            (Self::Tuple(elements), Expression::Array(items)) => {
+                if elements.len() != items.len() {
+                    return Err(format!(
+                        "expected {} with {} elements, got {}{}",
+                        self,
+                        elements.len(),
+                        items.len(),
+                        location(path)
+                    ));
+                }
+                for (i, (element, item)) in elements.iter().zip(items.iter()).enumerate() {
+                    element.check_at(item, &format!("{}[{}]", path, i))?;
+                }
+                Ok(())
+            }
+            (Self::Map(element), Expression::Object(object)) => {
+                for (key, value) in object.iter() {
+                    let key = TerraformUtils::object_key_to_string(key).unwrap_or_default();
+                    element.check_at(value.expr(), &format!("{}[\"{}\"]", path, key))?;
+                }
+                Ok(())
+            }
+            (Self::Object(attributes), Expression::Object(object)) => {
+                let values: Vec<(String, &Expression)> = object
+                    .iter()
+                    .filter_map(|(key, value)| Some((TerraformUtils::object_key_to_string(key)?, value.expr())))
+                    .collect();
+                for attribute in attributes {
+                    let attribute_path = format!("{}.{}", path, attribute.name);
+                    match values.iter().find(|(name, _)| *name == attribute.name) {
+                        Some((_, value)) => attribute.constraint.check_at(value, &attribute_path)?,
+                        None if attribute.optional => {}
+                        None => {
+                            return Err(format!(
+                                "missing required attribute '{}'",
+                                attribute_path.trim_start_matches('.')
+                            ));
+                        }
+                    }
+                }
+                Ok(())
+            }
+            _ => mismatch(),
+        }
+    }
+}
+
+impl ObjectAttribute {
+    fn parse(key: &ObjectKey, value: &Expression) -> Option<Self> {
+        let name = TerraformUtils::object_key_to_string(key)?;
+        match value {
+            Expression::FuncCall(call)
+                if call.name.namespace.is_empty() && call.name.name.as_str() == "optional" =>
+            {
+                // optional(type) or optional(type, default)
+                Some(Self {
+                    name,
+                    constraint: TypeConstraint::parse(call.args.iter().next()?)?,
+                    optional: true,
+                })
+            }
+            _ => Some(Self {
+                name,
+                constraint: TypeConstraint::parse(value)?,
+                optional: false,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for TypeConstraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Any => write!(f, "any"),
+            Self::String => write!(f, "string"),
+            Self::Number => write!(f, "number"),
+            Self::Bool => write!(f, "bool"),
+            Self::List(element) => write!(f, "list({})", element),
+            Self::Set(element) => write!(f, "set({})", element),
+            Self::Map(element) => write!(f, "map({})", element),
+            Self::Tuple(elements) => {
+                let elements: Vec<String> = elements.iter().map(ToString::to_string).collect();
+                write!(f, "tuple([{}])", elements.join(", "))
+            }
+            Self::Object(attributes) => {
+                let attributes: Vec<String> = attributes
+                    .iter()
+                    .map(|a| {
+                        if a.optional {
+                            format!("{} = optional({})", a.name, a.constraint)
+                        } else {
+                            format!("{} = {}", a.name, a.constraint)
+                        }
+                    })
+                    .collect();
+                write!(f, "object({{ {} }})", attributes.join(", "))
+            }
+        }
+    }
+}
+
+/// ` at <path>` suffix for messages about nested values
+fn location(path: &str) -> String {
+    if path.is_empty() {
+        String::new()
+    } else {
+        format!(" at {}", path)
+    }
+}
+
+/// A template without interpolations or directives, such as a plain heredoc
+pub fn is_literal_string(value: &Expression) -> bool {
+    let template = match value {
+        Expression::StringTemplate(template) => &**template,
+        Expression::HeredocTemplate(heredoc) => &heredoc.template,
+        _ => return false,
+    };
+    template.iter().all(|element| matches!(element, Element::Literal(_)))
+}
+
+/// Short description of a literal value's kind for messages
+fn describe(value: &Expression) -> &'static str {
+    match value {
+        Expression::Null(_) => "null",
+        Expression::Bool(_) => "bool",
+        Expression::Number(_) | Expression::UnaryOp(_) => "number",
+        Expression::String(_) | Expression::StringTemplate(_) | Expression::HeredocTemplate(_) => "string",
+        Expression::Array(_) => "list",
+        Expression::Object(_) => "object",
+        _ => "expression",
+    }
+}
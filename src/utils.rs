@@ -1,14 +1,81 @@
-use forseti_sdk::core::{Diagnostic, Position, Range};
+use forseti_sdk::core::{Fix, Position, Range, SuggestFix};
 use forseti_sdk::ruleset::RuleContext;
-use hcl::{Block, BlockLabel, Body};
+use hcl::edit::Span;
+use hcl::edit::expr::{Expression, ObjectKey};
+use hcl::edit::parser;
+use hcl::edit::structure::{Block, BlockLabel, Body};
+
+use crate::cache::DocumentCache;
+use crate::diagnostic::DiagnosticBuilder;
+use crate::lock_file::LockFile;
+use crate::options::TerraformRule;
 
 /// Shared utilities for Terraform engine rules
 pub struct TerraformUtils;
 
 impl TerraformUtils {
-    /// Parse HCL content and return Body, or None if parsing fails
-    pub fn parse_hcl(text: &str) -> Option<Body> {
-        hcl::from_str::<Body>(text).ok()
+    /// Parse HCL content into a Body that keeps source spans
+    pub fn parse_hcl(text: &str) -> Result<Body, parser::Error> {
+        parser::parse_body(text)
+    }
+
+    /// Whether a file uses Terraform's JSON syntax (`*.tf.json`, `*.tfvars.json`) or is
+    /// a JSON state file
+    pub fn is_json_syntax(uri: &str) -> bool {
+        uri.ends_with(".tf.json") || uri.ends_with(".tfvars.json") || Self::is_state_file(uri)
+    }
+
+    /// Whether a file is Terraform state (`*.tfstate`, `*.tfstate.backup`)
+    pub fn is_state_file(uri: &str) -> bool {
+        uri.ends_with(".tfstate") || uri.ends_with(".tfstate.backup")
+    }
+
+    /// Whether a file holds variable values (`*.tfvars`, `*.tfvars.json`)
+    pub fn is_tfvars(uri: &str) -> bool {
+        uri.ends_with(".tfvars") || uri.ends_with(".tfvars.json")
+    }
+
+    /// Whether a file is an override file (`override.tf`, `*_override.tf` or their
+    /// `.tf.json` forms), whose blocks Terraform merges into existing declarations
+    pub fn is_override_file(uri: &str) -> bool {
+        let name = uri.rsplit('/').next().unwrap_or(uri);
+        let stem = name
+            .strip_suffix(".tf.json")
+            .or_else(|| name.strip_suffix(".tf"));
+        stem.is_some_and(|stem| stem == "override" || stem.ends_with("_override"))
+    }
+
+    /// Whether `name` is a valid HCL identifier
+    pub fn is_identifier(name: &str) -> bool {
+        let mut chars = name.chars();
+        chars.next().is_some_and(|c| c.is_alphabetic() || c == '_')
+            && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+    }
+
+    /// Regex source (unanchored) for a glob where `*` and `?` stay within one segment,
+    /// segments being delimited by `separator`, and `**` spans any number of segments
+    pub fn glob_pattern(glob: &str, separator: char) -> String {
+        let segment = regex::escape(&separator.to_string());
+        let mut pattern = String::new();
+        let mut chars = glob.chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    // `**/` also matches no directory at all
+                    if chars.peek() == Some(&separator) {
+                        chars.next();
+                        pattern.push_str(&format!("(.*{})?", segment));
+                    } else {
+                        pattern.push_str(".*");
+                    }
+                }
+                '*' => pattern.push_str(&format!("[^{}]*", segment)),
+                '?' => pattern.push_str(&format!("[^{}]", segment)),
+                other => pattern.push_str(&regex::escape(&other.to_string())),
+            }
+        }
+        pattern
     }
 
     /// Convert byte offset to LSP Position
@@ -35,26 +102,44 @@ impl TerraformUtils {
         Position { line, character }
     }
 
-    /// Extract the string value from a BlockLabel
-    pub fn block_label_to_string(label: &BlockLabel) -> String {
-        format!("{:?}", label).trim_matches('"').to_string()
+    /// Convert a parser byte span to an LSP Range
+    pub fn span_to_range(span: &std::ops::Range<usize>, text: &str) -> Range {
+        Range {
+            start: Self::offset_to_position(span.start, text),
+            end: Self::offset_to_position(span.end, text),
+        }
+    }
+
+    /// Span of a block label's text, excluding the surrounding quotes
+    pub fn label_span(label: &BlockLabel, text: &str) -> std::ops::Range<usize> {
+        let span = label.span().unwrap_or(0..0);
+        if label.is_string() && text.get(span.clone()).is_some_and(|s| s.starts_with('"')) {
+            span.start + 1..span.end.saturating_sub(1).max(span.start + 1)
+        } else {
+            span
+        }
     }
 
-    /// Extract the string value from any Debug-formattable type (for ObjectKey, etc.)
-    pub fn debug_to_string<T: std::fmt::Debug>(item: &T) -> String {
-        format!("{:?}", item).trim_matches('"').to_string()
+    /// Extract the string value of an object key (`key = ...` or `"key" = ...`)
+    pub fn object_key_to_string(key: &ObjectKey) -> Option<String> {
+        match key {
+            ObjectKey::Ident(ident) => Some(ident.as_str().to_string()),
+            ObjectKey::Expression(Expression::String(value)) => Some(value.value().clone()),
+            ObjectKey::Expression(Expression::Variable(ident)) => Some(ident.as_str().to_string()),
+            ObjectKey::Expression(_) => None,
+        }
     }
 
-    /// Get the name of a block (first label for most blocks, second for resource/data)
-    pub fn get_block_name(block: &Block, block_type: &str) -> Option<String> {
+    /// Get the name label of a block (first label for most blocks, second for resource/data)
+    pub fn get_block_name<'a>(block: &'a Block, block_type: &str) -> Option<&'a BlockLabel> {
         match block_type {
             "resource" | "data" => {
                 // resource "aws_instance" "my_instance" - get "my_instance"
-                block.labels().get(1).map(Self::block_label_to_string)
+                block.labels.get(1)
             }
-            "variable" | "output" | "locals" => {
+            "variable" | "output" | "locals" | "module" => {
                 // variable "my_var" - get "my_var"
-                block.labels().first().map(Self::block_label_to_string)
+                block.labels.first()
             }
             _ => None,
         }
@@ -64,102 +149,186 @@ impl TerraformUtils {
     pub fn create_missing_description_diagnostic(
         rule_id: &str,
         block_type: &str,
-        block_name: &str,
+        block: &Block,
+        label: &BlockLabel,
         text: &str,
-    ) -> Diagnostic {
-        let block_text = format!("{} \"{}\"", block_type, block_name);
-        let block_start = text.find(&block_text).unwrap_or(0);
-
-        let start_pos = Self::offset_to_position(block_start, text);
-        let end_pos = Self::offset_to_position(block_start + block_text.len(), text);
+    ) -> DiagnosticBuilder {
+        let block_name = label.as_str();
+        let span = Self::label_span(label, text);
 
-        Diagnostic {
-            rule_id: rule_id.to_string(),
-            message: format!(
+        DiagnosticBuilder::new(
+            rule_id,
+            format!(
                 "{} '{}' should have a description",
                 Self::capitalize_first(block_type),
                 block_name
             ),
-            severity: "warn".to_string(),
-            range: Range {
-                start: start_pos,
-                end: end_pos,
-            },
-            code: Some("MISSING_DESCRIPTION".to_string()),
-            suggest: None,
-            docs_url: Some(format!("https://forseti.dev/rules/terraform/{}", rule_id)),
-        }
+        )
+        .with_span(&span, text)
+        .with_code("MISSING_DESCRIPTION")
+        .with_suggestions(Self::create_description_fix(block, block_name, text).map(|fix| vec![fix]))
     }
 
     /// Create a diagnostic for naming convention violations
     pub fn create_naming_convention_diagnostic(
         block_type: &str,
         block_name: &str,
+        convention: &str,
+        span: &std::ops::Range<usize>,
         text: &str,
-    ) -> Diagnostic {
-        // Try to find the specific name position for better highlighting
-        let name_pattern = format!("\"{}\"", block_name);
-        let name_start = if let Some(pos) = text.find(&name_pattern) {
-            pos + 1 // Skip opening quote
-        } else {
-            text.find(block_name).unwrap_or(0)
-        };
-
-        let start_pos = Self::offset_to_position(name_start, text);
-        let end_pos = Self::offset_to_position(name_start + block_name.len(), text);
-
-        Diagnostic {
-            rule_id: "resource-naming-convention".to_string(),
-            message: format!(
-                "{} name '{}' should follow snake_case convention",
-                block_type, block_name
+    ) -> DiagnosticBuilder {
+        DiagnosticBuilder::new(
+            "resource-naming-convention",
+            format!(
+                "{} name '{}' should follow {}",
+                block_type, block_name, convention
             ),
-            severity: "warn".to_string(),
-            range: Range {
-                start: start_pos,
-                end: end_pos,
-            },
-            code: Some("NAMING_CONVENTION".to_string()),
-            suggest: None,
-            docs_url: Some(
-                "https://forseti.dev/rules/terraform/resource-naming-convention".to_string(),
-            ),
-        }
+        )
+        .with_span(span, text)
+        .with_code("NAMING_CONVENTION")
     }
 
     /// Create a diagnostic for missing provider version
-    pub fn create_provider_version_diagnostic(provider_name: &str, text: &str) -> Diagnostic {
-        let provider_text = format!("{} =", provider_name);
-        let provider_start = text.find(&provider_text).unwrap_or(0);
-
-        let start_pos = Self::offset_to_position(provider_start, text);
-        let end_pos = Self::offset_to_position(provider_start + provider_name.len(), text);
-
-        Diagnostic {
-            rule_id: "require-provider-version".to_string(),
-            message: format!(
+    pub fn create_provider_version_diagnostic(
+        provider_name: &str,
+        span: &std::ops::Range<usize>,
+        text: &str,
+    ) -> DiagnosticBuilder {
+        DiagnosticBuilder::new(
+            "require-provider-version",
+            format!(
                 "Provider '{}' should specify a version constraint",
                 provider_name
             ),
-            severity: "warn".to_string(),
-            range: Range {
-                start: start_pos,
-                end: end_pos,
-            },
-            code: Some("PROVIDER_VERSION".to_string()),
-            suggest: None,
-            docs_url: Some(
-                "https://forseti.dev/rules/terraform/require-provider-version".to_string(),
-            ),
+        )
+        .with_span(span, text)
+        .with_code("PROVIDER_VERSION")
+    }
+
+    /// Build a fix that inserts `description = "..."` as the first attribute of a block.
+    ///
+    /// The placeholder is derived from the block name (`instance_type` -> "Instance type").
+    /// Returns None for one-line blocks with content, which cannot hold a second attribute
+    /// without being reformatted.
+    pub fn create_description_fix(block: &Block, block_name: &str, text: &str) -> Option<SuggestFix> {
+        let header_end = block
+            .labels
+            .last()
+            .and_then(|label| label.span())
+            .or_else(|| block.ident.span())?
+            .end;
+        let open_brace = header_end + text.get(header_end..)?.find('{')?;
+        if text[header_end..open_brace].contains(':') {
+            return Self::create_json_description_fix(block, block_name, text, open_brace);
+        }
+        let block_indent = Self::line_indent(text, open_brace);
+        let placeholder = Self::humanize_name(block_name);
+
+        let (start, end, insert) = match block.body.iter().next().and_then(|s| s.span()) {
+            Some(first) if text[open_brace..first.start].contains('\n') => {
+                // Insert a new line right after the opening brace, indented like the body
+                let line_start = open_brace + text[open_brace..].find('\n')? + 1;
+                let indent = Self::line_indent(text, first.start);
+                let insert = format!("{}description = \"{}\"\n", indent, placeholder);
+                (line_start, line_start, insert)
+            }
+            Some(_) => return None,
+            None => {
+                // Empty body: expand `{}` (or `{ }`) into a multi-line block
+                let close_brace = open_brace + text[open_brace..].find('}')?;
+                let insert = format!(
+                    "{{\n{}  description = \"{}\"\n{}}}",
+                    block_indent, placeholder, block_indent
+                );
+                (open_brace, close_brace + 1, insert)
+            }
+        };
+
+        Some(SuggestFix {
+            title: format!("Add description \"{}\"", placeholder),
+            fix: Some(Fix {
+                range: Self::span_to_range(&(start..end), text),
+                text: insert,
+            }),
+        })
+    }
+
+    /// [`Self::create_description_fix`] for Terraform JSON syntax, where the label is
+    /// followed by `: {` and the description becomes the object's first property
+    fn create_json_description_fix(
+        block: &Block,
+        block_name: &str,
+        text: &str,
+        open_brace: usize,
+    ) -> Option<SuggestFix> {
+        let placeholder = Self::humanize_name(block_name);
+        let property = format!("\"description\": \"{}\"", placeholder);
+
+        // Items start at their identifier, just inside the key's opening quote
+        let (offset, insert) = match block.body.iter().next().and_then(|s| s.span()) {
+            Some(first) => {
+                let key_start = first.start.checked_sub(1)?;
+                let separator = &text[open_brace + 1..key_start];
+                (key_start, format!("{},{}", property, separator))
+            }
+            None => (open_brace + 1, property),
+        };
+
+        Some(SuggestFix {
+            title: format!("Add description \"{}\"", placeholder),
+            fix: Some(Fix {
+                range: Self::span_to_range(&(offset..offset), text),
+                text: insert,
+            }),
+        })
+    }
+
+    /// Leading whitespace of the line containing `offset`
+    fn line_indent(text: &str, offset: usize) -> &str {
+        let line_start = text[..offset].rfind('\n').map_or(0, |i| i + 1);
+        let line = &text[line_start..];
+        let indent_len = line.len() - line.trim_start_matches([' ', '\t']).len();
+        &line[..indent_len]
+    }
+
+    /// Turn an identifier like `instance_type` into a readable phrase like "Instance type"
+    fn humanize_name(name: &str) -> String {
+        let words: Vec<&str> = name.split(['_', '-']).filter(|w| !w.is_empty()).collect();
+        Self::capitalize_first(&words.join(" "))
+    }
+
+    /// The candidate closest to `name` by edit distance, if it is close enough to be
+    /// a plausible typo
+    pub fn closest_match<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<&'a str> {
+        let max_distance = (name.chars().count() / 3).max(1);
+        candidates
+            .into_iter()
+            .map(|candidate| (Self::edit_distance(name, candidate), candidate))
+            .filter(|(distance, _)| *distance <= max_distance)
+            .min_by_key(|(distance, _)| *distance)
+            .map(|(_, candidate)| candidate)
+    }
+
+    /// Levenshtein distance between two strings, counted in characters
+    pub fn edit_distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+        for (i, ca) in a.chars().enumerate() {
+            let mut current = vec![i + 1; b.len() + 1];
+            for (j, cb) in b.iter().enumerate() {
+                let substitution = previous[j] + usize::from(ca != *cb);
+                current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+            }
+            previous = current;
         }
+
+        previous[b.len()]
     }
 
     /// Check if a block has a description attribute
     pub fn has_description_attribute(block: &Block) -> bool {
-        block
-            .body()
-            .attributes()
-            .any(|attr| attr.key() == "description")
+        block.body.has_attribute("description")
     }
 
     /// Capitalize first letter of a string
@@ -173,15 +342,90 @@ impl TerraformUtils {
 }
 
 /// Trait for rules that need common HCL parsing functionality
-pub trait HclRule {
+pub trait HclRule: TerraformRule {
+    /// Shared parse cache this rule reads its AST from
+    fn documents(&self) -> &DocumentCache;
+
     /// Check rule with HCL parsing handled automatically
     fn check_hcl(&self, body: &Body, ctx: &mut RuleContext);
 
-    /// Default implementation that handles HCL parsing
+    /// Default implementation that fetches the parsed file from the shared cache
     fn check(&self, ctx: &mut RuleContext) {
-        if let Some(body) = TerraformUtils::parse_hcl(ctx.text) {
-            self.check_hcl(&body, ctx);
+        if !self.enabled(ctx) {
+            return;
+        }
+
+        // The dependency lock file is HCL too, but only valid-lock-file reads it; state
+        // files have no configuration body at all
+        if LockFile::is_lock_file(ctx.uri) || TerraformUtils::is_state_file(ctx.uri) {
+            return;
+        }
+
+        let document = self.documents().get_or_parse(ctx.uri, ctx.text);
+        if let Some(body) = &document.body {
+            self.check_hcl(body, ctx);
         }
-        // If parsing fails, silently skip (file might be invalid HCL)
+        // Parse failures are reported once by the hcl-syntax-error rule
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cache::DocumentCache;
+    use crate::rules::VariableDescriptionRequiredRule;
+    use crate::testing::{apply_fix, run};
+    use serde_json::json;
+    use std::sync::Arc;
+
+    /// `text` with the missing-description fix of its first variable applied
+    fn add_description(uri: &str, text: &str) -> Option<String> {
+        let rule = VariableDescriptionRequiredRule::new(Arc::new(DocumentCache::new()));
+        let diagnostics = run(&rule, uri, text, json!("warn"));
+        apply_fix(text, diagnostics.first()?)
+    }
+
+    /// The description of the first block of `text`, which must parse
+    fn description(uri: &str, text: &str) -> Option<String> {
+        let document = DocumentCache::new().get_or_parse(uri, text);
+        assert!(document.error.is_none(), "{}", text);
+        let block = document.body.as_ref()?.blocks().next()?;
+        block.body.get_attribute("description")?.value.as_str().map(str::to_string)
+    }
+
+    #[test]
+    fn descriptions_are_added_as_the_first_attribute() {
+        let text = "variable \"instance_type\" {\n  type    = string\n  default = \"t3.micro\"\n}\n";
+        let fixed = add_description("file:///main.tf", text).unwrap();
+        assert_eq!(
+            fixed,
+            "variable \"instance_type\" {\n  description = \"Instance type\"\n  type    = string\n  default = \"t3.micro\"\n}\n"
+        );
+        assert_eq!(description("file:///main.tf", &fixed).as_deref(), Some("Instance type"));
+    }
+
+    #[test]
+    fn empty_one_line_blocks_are_expanded() {
+        let fixed = add_description("file:///main.tf", "  variable \"x\" {}\n").unwrap();
+        assert_eq!(fixed, "  variable \"x\" {\n    description = \"X\"\n  }\n");
+        assert_eq!(description("file:///main.tf", &fixed).as_deref(), Some("X"));
+
+        // One-line blocks with content are left alone rather than reformatted
+        assert!(add_description("file:///main.tf", "variable \"x\" { type = string }\n").is_none());
+    }
+
+    #[test]
+    fn json_syntax_gets_a_description_property() {
+        let text = "{\n  \"variable\": {\n    \"region\": {\n      \"type\": \"string\"\n    }\n  }\n}\n";
+        let fixed = add_description("file:///variables.tf.json", text).unwrap();
+        assert_eq!(
+            fixed,
+            "{\n  \"variable\": {\n    \"region\": {\n      \"description\": \"Region\",\n      \"type\": \"string\"\n    }\n  }\n}\n"
+        );
+        assert_eq!(description("file:///variables.tf.json", &fixed).as_deref(), Some("Region"));
+
+        let text = "{\"variable\": {\"zone\": {}}}";
+        let fixed = add_description("file:///variables.tf.json", text).unwrap();
+        assert_eq!(fixed, "{\"variable\": {\"zone\": {\"description\": \"Zone\"}}}");
+        assert_eq!(description("file:///variables.tf.json", &fixed).as_deref(), Some("Zone"));
     }
 }
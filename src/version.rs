@@ -0,0 +1,389 @@
+//! Terraform version numbers and version constraint strings (`">= 1.2, < 2.0"`,
+//! `"~> 5.0"`), as used by `required_providers`, `required_version` and module calls.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A version such as `1.2.3` or `1.5.0-beta1`
+#[derive(Debug, Clone)]
+pub struct Version {
+    /// Numeric segments as written; missing segments compare as zero
+    pub segments: Vec<u64>,
+    pub prerelease: Option<String>,
+}
+
+impl Version {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        let text = text.strip_prefix('v').unwrap_or(text);
+        // Build metadata never affects precedence
+        let text = text.split_once('+').map_or(text, |(version, _)| version);
+        let (numbers, prerelease) = match text.split_once('-') {
+            Some((numbers, prerelease)) if !prerelease.is_empty() => {
+                (numbers, Some(prerelease.to_string()))
+            }
+            Some(_) => return None,
+            None => (text, None),
+        };
+
+        let segments = numbers
+            .split('.')
+            .map(|segment| {
+                if segment.is_empty() || !segment.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                segment.parse().ok()
+            })
+            .collect::<Option<Vec<u64>>>()?;
+        if segments.len() > 3 {
+            return None;
+        }
+
+        Some(Self {
+            segments,
+            prerelease,
+        })
+    }
+
+    fn segment(&self, index: usize) -> u64 {
+        self.segments.get(index).copied().unwrap_or(0)
+    }
+
+    /// The smallest release after this version (`1.4` -> `1.4.1`)
+    fn next_patch(&self) -> Version {
+        Version {
+            segments: vec![self.segment(0), self.segment(1), self.segment(2) + 1],
+            prerelease: None,
+        }
+    }
+
+    /// The exclusive upper bound of `~>` with this version: the last written segment
+    /// may grow, so the one before it is incremented (`~> 1.2` -> `2.0`, `~> 1.2.3` -> `1.3.0`)
+    fn pessimistic_bound(&self) -> Version {
+        let bumped = self.segments.len().saturating_sub(2);
+        let mut segments: Vec<u64> = self.segments.iter().take(bumped + 1).copied().collect();
+        if let Some(last) = segments.last_mut() {
+            *last += 1;
+        }
+        Version {
+            segments,
+            prerelease: None,
+        }
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let numeric = (0..3)
+            .map(|i| self.segment(i).cmp(&other.segment(i)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal);
+        numeric.then_with(|| match (&self.prerelease, &other.prerelease) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(_), None) => Ordering::Less,
+            (Some(a), Some(b)) => compare_prerelease(a, b),
+        })
+    }
+}
+
+impl PartialEq for Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for Version {}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Semver precedence of pre-release identifiers: numeric parts compare as numbers
+fn compare_prerelease(a: &str, b: &str) -> Ordering {
+    let mut a_parts = a.split('.');
+    let mut b_parts = b.split('.');
+    loop {
+        return match (a_parts.next(), b_parts.next()) {
+            (None, None) => Ordering::Equal,
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(x), Some(y)) => {
+                let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+                    (Ok(x), Ok(y)) => x.cmp(&y),
+                    (Ok(_), Err(_)) => Ordering::Less,
+                    (Err(_), Ok(_)) => Ordering::Greater,
+                    (Err(_), Err(_)) => x.cmp(y),
+                };
+                if ordering.is_eq() {
+                    continue;
+                }
+                ordering
+            }
+        };
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let segments: Vec<String> = self.segments.iter().map(u64::to_string).collect();
+        write!(f, "{}", segments.join("."))?;
+        if let Some(prerelease) = &self.prerelease {
+            write!(f, "-{}", prerelease)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    Greater,
+    GreaterOrEqual,
+    Less,
+    LessOrEqual,
+    /// `~>`: allows only the rightmost written segment to increase
+    Pessimistic,
+}
+
+impl Operator {
+    const ALL: [(&'static str, Operator); 7] = [
+        ("~>", Operator::Pessimistic),
+        (">=", Operator::GreaterOrEqual),
+        ("<=", Operator::LessOrEqual),
+        ("!=", Operator::NotEqual),
+        (">", Operator::Greater),
+        ("<", Operator::Less),
+        ("=", Operator::Equal),
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        Self::ALL
+            .iter()
+            .find(|(_, operator)| *operator == self)
+            .map_or("=", |(symbol, _)| symbol)
+    }
+}
+
+/// One comparison such as `>= 1.2`
+#[derive(Debug, Clone)]
+pub struct Constraint {
+    pub operator: Operator,
+    pub version: Version,
+}
+
+impl Constraint {
+    fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("empty version constraint".to_string());
+        }
+        let (operator, rest) = Operator::ALL
+            .iter()
+            .find_map(|(symbol, operator)| text.strip_prefix(symbol).map(|rest| (*operator, rest)))
+            .unwrap_or((Operator::Equal, text));
+
+        let version = Version::parse(rest)
+            .ok_or_else(|| format!("'{}' is not a valid version constraint", text))?;
+        if operator == Operator::Pessimistic && version.prerelease.is_some() {
+            return Err(format!(
+                "'{}' cannot combine '~>' with a pre-release version",
+                text
+            ));
+        }
+        Ok(Self { operator, version })
+    }
+
+    fn matches(&self, version: &Version) -> bool {
+        let target = &self.version;
+        match self.operator {
+            Operator::Equal => version == target,
+            Operator::NotEqual => version != target,
+            Operator::Greater => version > target,
+            Operator::GreaterOrEqual => version >= target,
+            Operator::Less => version < target,
+            Operator::LessOrEqual => version <= target,
+            Operator::Pessimistic => version >= target && *version < target.pessimistic_bound(),
+        }
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.operator.as_str(), self.version)
+    }
+}
+
+/// A comma-separated list of constraints, all of which must hold
+#[derive(Debug, Clone)]
+pub struct VersionConstraints {
+    pub constraints: Vec<Constraint>,
+}
+
+impl VersionConstraints {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let constraints = text
+            .split(',')
+            .map(Constraint::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { constraints })
+    }
+
+    /// Whether `version` satisfies every constraint. Pre-releases are only selected
+    /// by an exact `=` constraint naming them, as Terraform does.
+    pub fn matches(&self, version: &Version) -> bool {
+        if version.prerelease.is_some()
+            && !self
+                .constraints
+                .iter()
+                .any(|c| c.operator == Operator::Equal && c.version.prerelease.is_some())
+        {
+            return false;
+        }
+        self.constraints
+            .iter()
+            .all(|constraint| constraint.matches(version))
+    }
+    /// The highest lower bound set by the constraints, and whether it is inclusive
+    pub fn lower_bound(&self) -> Option<(&Version, bool)> {
+        self.constraints
+            .iter()
+            .filter_map(|constraint| match constraint.operator {
+                Operator::Equal | Operator::GreaterOrEqual | Operator::Pessimistic => {
+                    Some((&constraint.version, true))
+                }
+                Operator::Greater => Some((&constraint.version, false)),
+                _ => None,
+            })
+            .max_by(|(a, a_inclusive), (b, b_inclusive)| {
+                a.cmp(b).then(b_inclusive.cmp(a_inclusive))
+            })
+    }
+
+    /// Whether some release older than `minimum` satisfies the lower bound
+    pub fn allows_below(&self, minimum: &Version) -> bool {
+        match self.lower_bound() {
+            None => true,
+            Some((bound, true)) => bound < minimum,
+            Some((bound, false)) => bound.next_patch() < *minimum,
+        }
+    }
+    /// Whether any constraint caps the version from above
+    pub fn has_upper_bound(&self) -> bool {
+        self.constraints.iter().any(|constraint| {
+            matches!(
+                constraint.operator,
+                Operator::Less | Operator::LessOrEqual | Operator::Pessimistic | Operator::Equal
+            )
+        })
+    }
+
+    pub fn uses_operator(&self, operator: Operator) -> bool {
+        self.constraints
+            .iter()
+            .any(|constraint| constraint.operator == operator)
+    }
+
+    /// Whether at least one version satisfies every constraint. The allowed set is a
+    /// range minus the `!=` exclusions, so it is enough to try the releases at and just
+    /// above its lower bound.
+    pub fn is_satisfiable(&self) -> bool {
+        let start = match self.lower_bound() {
+            Some((bound, true)) => bound.clone(),
+            Some((bound, false)) => bound.next_patch(),
+            None => Version {
+                segments: vec![0, 0, 0],
+                prerelease: None,
+            },
+        };
+        std::iter::successors(Some(start), |version| Some(version.next_patch()))
+            .take(self.constraints.len() + 1)
+            .any(|version| self.matches(&version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> Version {
+        Version::parse(text).unwrap_or_else(|| panic!("{} is a valid version", text))
+    }
+
+    fn allows(constraints: &str, candidate: &str) -> bool {
+        VersionConstraints::parse(constraints)
+            .unwrap()
+            .matches(&version(candidate))
+    }
+
+    #[test]
+    fn parses_versions() {
+        let parsed = version("v1.5.0-beta.2+build.7");
+        assert_eq!(parsed.segments, [1, 5, 0]);
+        assert_eq!(parsed.prerelease.as_deref(), Some("beta.2"));
+        assert_eq!(version("1.2").to_string(), "1.2");
+        assert_eq!(version("1.2"), version("1.2.0"));
+        for invalid in ["", "1..2", "1.2.3.4", "1.x", "1.2-", "-1"] {
+            assert!(Version::parse(invalid).is_none(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parses_constraints() {
+        let constraints = VersionConstraints::parse(">= 1.2, < 2.0,!=1.5.1").unwrap();
+        let written: Vec<String> = constraints
+            .constraints
+            .iter()
+            .map(Constraint::to_string)
+            .collect();
+        assert_eq!(written, [">= 1.2", "< 2.0", "!= 1.5.1"]);
+        assert_eq!(
+            VersionConstraints::parse("1.0").unwrap().constraints[0].operator,
+            Operator::Equal
+        );
+        assert!(VersionConstraints::parse("").is_err());
+        assert!(VersionConstraints::parse(">= 1.0,").is_err());
+        assert!(VersionConstraints::parse("=> 1.0").is_err());
+        assert!(VersionConstraints::parse("~> 1.0-beta").is_err());
+    }
+
+    #[test]
+    fn pessimistic_constraint_bounds() {
+        assert!(allows("~> 1.2", "1.2.0"));
+        assert!(allows("~> 1.2", "1.9.9"));
+        assert!(!allows("~> 1.2", "2.0.0"));
+        assert!(!allows("~> 1.2", "1.1.9"));
+        assert!(allows("~> 1.2.3", "1.2.9"));
+        assert!(!allows("~> 1.2.3", "1.3.0"));
+        assert!(allows("~> 1", "1.9.0"));
+        assert!(!allows("~> 1", "2.0.0"));
+    }
+
+    #[test]
+    fn prereleases_order_before_their_release() {
+        assert!(version("1.0.0-alpha") < version("1.0.0-alpha.1"));
+        assert!(version("1.0.0-alpha.1") < version("1.0.0-alpha.beta"));
+        assert!(version("1.0.0-beta.2") < version("1.0.0-beta.11"));
+        assert!(version("1.0.0-rc.1") < version("1.0.0"));
+        assert!(version("1.0.0") < version("1.0.1-alpha"));
+        // Only an exact constraint selects a pre-release
+        assert!(!allows(">= 1.0.0-alpha", "1.0.0-beta"));
+        assert!(allows("= 1.0.0-beta", "1.0.0-beta"));
+    }
+
+    #[test]
+    fn detects_unsatisfiable_constraints() {
+        let satisfiable = |text: &str| VersionConstraints::parse(text).unwrap().is_satisfiable();
+        assert!(satisfiable(">= 1.0, < 2.0"));
+        assert!(satisfiable("> 1.0, < 1.0.2"));
+        assert!(satisfiable("~> 1.2, != 1.2.0"));
+        assert!(satisfiable("= 1.0.0-beta"));
+        assert!(!satisfiable(">= 2.0, < 1.0"));
+        assert!(!satisfiable("> 1.0, < 1.0.1"));
+        assert!(!satisfiable("= 1.0, != 1.0.0"));
+        assert!(!satisfiable("~> 1.2.3, >= 1.3"));
+    }
+}